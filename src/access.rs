@@ -1,24 +1,73 @@
-use std::any::{type_name, TypeId};
+use std::{
+    any::{type_name, TypeId},
+    sync::Mutex,
+};
 
 use hecs::{Fetch, Query};
 
-use crate::borrow::ComponentBorrow;
+use crate::{borrow::ComponentBorrow, filter::ArchetypeFilter};
+
+#[derive(PartialOrd, Ord, Eq, PartialEq)]
+/// The components an access is restricted to, given by id and name
+struct AccessFilter {
+    with: Vec<(TypeId, &'static str)>,
+    without: Vec<(TypeId, &'static str)>,
+}
+
+static UNFILTERED: AccessFilter = AccessFilter {
+    with: Vec::new(),
+    without: Vec::new(),
+};
+
+/// Returns a static copy of `filter`, such that accesses remain `Copy`. Each
+/// distinct filter is only allocated once.
+fn intern(filter: AccessFilter) -> &'static AccessFilter {
+    static FILTERS: Mutex<Vec<&'static AccessFilter>> = Mutex::new(Vec::new());
+
+    if filter == UNFILTERED {
+        return &UNFILTERED;
+    }
+
+    let mut filters = FILTERS.lock().unwrap();
+    match filters.iter().find(|interned| ***interned == filter) {
+        Some(interned) => interned,
+        None => {
+            let interned = Box::leak(Box::new(filter));
+            filters.push(interned);
+            interned
+        }
+    }
+}
 
 #[derive(Copy, Clone, PartialOrd, Ord, Eq, PartialEq)]
 /// Describes how a type is accessed.
+///
+/// The access can be restricted to the entities having all `with` and none of
+/// the `without` components, see [`filter`](crate::filter).
 pub struct Access {
     pub(crate) name: &'static str,
     pub(crate) id: TypeId,
     pub(crate) exclusive: bool,
+    filter: &'static AccessFilter,
 }
 
 impl std::fmt::Debug for Access {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.exclusive {
-            write!(f, "mut {}", self.name)
+            write!(f, "mut {}", self.name)?;
         } else {
-            write!(f, "{}", self.name)
+            write!(f, "{}", self.name)?;
+        }
+
+        for (_, name) in &self.filter.with {
+            write!(f, " with {}", name)?;
         }
+
+        for (_, name) in &self.filter.without {
+            write!(f, " without {}", name)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -29,9 +78,29 @@ impl Access {
             name,
             id,
             exclusive,
+            filter: &UNFILTERED,
         }
     }
 
+    /// Restricts the access to the entities passing the filter `F`
+    pub fn filtered<F: ArchetypeFilter>(mut self) -> Self {
+        let mut filter = AccessFilter {
+            with: self.filter.with.clone(),
+            without: self.filter.without.clone(),
+        };
+
+        F::for_each_component(&mut |id, name, required| {
+            if required {
+                filter.with.push((id, name))
+            } else {
+                filter.without.push((id, name))
+            }
+        });
+
+        self.filter = intern(filter);
+        self
+    }
+
     /// Creates a new access from a known  type
     pub fn of<T: IntoAccess>() -> Self {
         T::access()
@@ -54,6 +123,33 @@ impl Access {
     pub fn name(&self) -> &'static str {
         self.name
     }
+
+    /// Returns the components the accessed entities are required to have
+    pub fn with(&self) -> impl Iterator<Item = TypeId> + '_ {
+        self.filter.with.iter().map(|(id, _)| *id)
+    }
+
+    /// Returns the components the accessed entities are required to lack
+    pub fn without(&self) -> impl Iterator<Item = TypeId> + '_ {
+        self.filter.without.iter().map(|(id, _)| *id)
+    }
+
+    /// Returns true if no entity can be accessed by both `self` and `other`,
+    /// as one requires a component the other excludes.
+    pub fn is_disjoint(&self, other: &Access) -> bool {
+        self.with()
+            .any(|id| other.without().any(|other| other == id))
+            || other
+                .with()
+                .any(|id| self.without().any(|other| other == id))
+    }
+
+    /// Returns true if the accesses can not be held at the same time, which
+    /// is the case if they access the same type, at least one of them
+    /// exclusively, and the accessed entities may overlap.
+    pub fn conflicts(&self, other: &Access) -> bool {
+        self.id == other.id && (self.exclusive || other.exclusive) && !self.is_disjoint(other)
+    }
 }
 
 /// Convert a type into the correspodning access.
@@ -73,21 +169,13 @@ pub trait IntoAccess {
 
 impl<T: 'static> IntoAccess for &T {
     fn access() -> Access {
-        Access {
-            id: TypeId::of::<T>(),
-            exclusive: false,
-            name: type_name::<T>(),
-        }
+        Access::new(type_name::<T>(), TypeId::of::<T>(), false)
     }
 }
 
 impl<T: 'static> IntoAccess for &mut T {
     fn access() -> Access {
-        Access {
-            id: TypeId::of::<T>(),
-            exclusive: true,
-            name: type_name::<T>(),
-        }
+        Access::new(type_name::<T>(), TypeId::of::<T>(), true)
     }
 }
 
@@ -100,7 +188,7 @@ pub trait Subset {
     fn is_subset<U: ComponentBorrow>() -> bool;
 }
 
-impl<'a, Q: Query> Subset for Q {
+impl<Q: Query> Subset for Q {
     fn is_subset<U: ComponentBorrow>() -> bool {
         let mut all = true;
         Q::Fetch::for_each_borrow(|id, exclusive| {
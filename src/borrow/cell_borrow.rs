@@ -1,8 +1,9 @@
 use std::{
-    any::type_name,
+    any::{type_name, TypeId},
     marker::PhantomData,
     ops::{Deref, DerefMut},
     ptr::NonNull,
+    sync::atomic::{AtomicU64, Ordering},
 };
 
 /// Type alias for list of borrows
@@ -11,18 +12,25 @@ pub type Borrows = SmallVec<[Access; 8]>;
 use atomic_refcell::{AtomicRef, AtomicRefCell, AtomicRefMut};
 use smallvec::{smallvec, SmallVec};
 
-use crate::{Access, Context, Error, Result};
+use crate::{Access, Context, Error, Result, Tick};
 
 use super::ComponentBorrow;
 
 /// Wrapper type for an immutably borrowed value from schedule context
-#[repr(transparent)]
 #[derive(Debug)]
-pub struct Read<'a, T>(pub(crate) AtomicRef<'a, T>);
+pub struct Read<'a, T> {
+    borrow: AtomicRef<'a, T>,
+    changed: Option<&'a AtomicU64>,
+    last_run: Tick,
+}
 
 impl<'a, T> Clone for Read<'a, T> {
     fn clone(&self) -> Self {
-        Self(AtomicRef::<'a, T>::clone(&self.0))
+        Self {
+            borrow: AtomicRef::<'a, T>::clone(&self.borrow),
+            changed: self.changed,
+            last_run: self.last_run,
+        }
     }
 }
 
@@ -30,14 +38,31 @@ impl<'a, T> Deref for Read<'a, T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.borrow
     }
 }
 
 impl<'a, T> Read<'a, T> {
     /// Creates a new Read borrow from an atomic ref
     pub fn new(borrow: AtomicRef<'a, T>) -> Self {
-        Self(borrow)
+        Self {
+            borrow,
+            changed: None,
+            last_run: Tick::default(),
+        }
+    }
+
+    /// Returns true if the value was mutably accessed through [`Write`] since
+    /// the system last ran.
+    ///
+    /// Always returns true if the value is not tracked, such as when not
+    /// executed by a [`Schedule`](crate::Schedule), or when not declared by
+    /// the borrows of the system.
+    pub fn is_changed(&self) -> bool {
+        match self.changed {
+            Some(changed) => changed.load(Ordering::Acquire) > self.last_run.0,
+            None => true,
+        }
     }
 }
 
@@ -45,18 +70,38 @@ impl<'a, T: 'static> Read<'a, T> {
     pub(crate) fn try_from_untyped(cell: &'a AtomicRefCell<NonNull<u8>>) -> Result<Self> {
         cell.try_borrow()
             .map_err(|_| Error::Borrow(type_name::<T>()))
-            .map(|cell| Self(AtomicRef::map(cell, |val| unsafe { val.cast().as_ref() })))
+            .map(|cell| Self::new(AtomicRef::map(cell, |val| unsafe { val.cast().as_ref() })))
+    }
+
+    fn try_from_context(context: &'a Context) -> Result<Self> {
+        let mut val = Self::try_from_untyped(context.cell::<&T>()?)?;
+        val.changed = context.resource_tick(TypeId::of::<BorrowMarker<T>>());
+        val.last_run = context.last_run();
+        Ok(val)
     }
 }
 
-#[repr(transparent)]
-/// Wrapper type for an exclusively borrowed value
-pub struct Write<'a, T>(pub(crate) AtomicRefMut<'a, T>);
+/// Wrapper type for an exclusively borrowed value.
+///
+/// Mutably dereferencing the value marks it as changed, which is observed by
+/// [`Read::is_changed`].
+pub struct Write<'a, T> {
+    borrow: AtomicRefMut<'a, T>,
+    changed: Option<&'a AtomicU64>,
+}
 
 impl<'a, T> Write<'a, T> {
     /// Creates a new Write borrow from an atomic ref
     pub fn new(borrow: AtomicRefMut<'a, T>) -> Self {
-        Self(borrow)
+        Self {
+            borrow,
+            changed: None,
+        }
+    }
+
+    /// Mutably accesses the value without marking it as changed
+    pub fn bypass_change_detection(&mut self) -> &mut T {
+        &mut self.borrow
     }
 }
 
@@ -65,24 +110,34 @@ impl<'a, T: 'static> Write<'a, T> {
         cell.try_borrow_mut()
             .map_err(|_| Error::BorrowMut(type_name::<T>()))
             .map(|cell| {
-                Self(AtomicRefMut::map(cell, |val| unsafe {
+                Self::new(AtomicRefMut::map(cell, |val| unsafe {
                     val.cast().as_mut()
                 }))
             })
     }
+
+    fn try_from_context(context: &'a Context) -> Result<Self> {
+        let mut val = Self::try_from_untyped(context.cell::<&mut T>()?)?;
+        val.changed = context.resource_tick(TypeId::of::<BorrowMarker<T>>());
+        Ok(val)
+    }
 }
 
 impl<'a, T> Deref for Write<'a, T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.borrow
     }
 }
 
 impl<'a, T> DerefMut for Write<'a, T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        if let Some(changed) = self.changed {
+            changed.store(Tick::now().0, Ordering::Release);
+        }
+
+        &mut self.borrow
     }
 }
 
@@ -103,7 +158,7 @@ impl<'a, T: 'static> ContextBorrow<'a> for &'a T {
     type Target = Read<'a, T>;
 
     fn borrow(context: &'a Context) -> Result<Self::Target> {
-        Read::try_from_untyped(context.cell::<&T>()?)
+        Read::try_from_context(context)
     }
 }
 
@@ -111,7 +166,7 @@ impl<'a, T: 'static> ContextBorrow<'a> for &'a mut T {
     type Target = Write<'a, T>;
 
     fn borrow(context: &'a Context) -> Result<Self::Target> {
-        Write::try_from_untyped(context.cell::<&mut T>()?)
+        Write::try_from_context(context)
     }
 }
 
@@ -119,7 +174,7 @@ impl<'a, T: 'static> ContextBorrow<'a> for Read<'a, T> {
     type Target = Self;
 
     fn borrow(context: &'a Context) -> Result<Self::Target> {
-        Read::try_from_untyped(context.cell::<&T>()?)
+        Read::try_from_context(context)
     }
 }
 
@@ -127,7 +182,7 @@ impl<'a, T: 'static> ContextBorrow<'a> for Write<'a, T> {
     type Target = Self;
 
     fn borrow(context: &'a Context) -> Result<Self::Target> {
-        Write::try_from_untyped(context.cell::<&mut T>()?)
+        Write::try_from_context(context)
     }
 }
 
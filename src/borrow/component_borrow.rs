@@ -1,7 +1,7 @@
 use std::any::{type_name, TypeId};
 
 use super::Borrows;
-use crate::{Access, AllAccess, IntoAccess};
+use crate::{system::SystemState, Access, AllAccess, IntoAccess};
 use hecs::{Fetch, Query, World};
 pub use smallvec::smallvec;
 use smallvec::SmallVec;
@@ -14,9 +14,12 @@ pub trait ComponentBorrow {
     fn has_dynamic(id: TypeId, exclusive: bool) -> bool;
     /// Returns true if U exists in Self
     fn has<U: IntoAccess>() -> bool;
+    /// Initializes the per system state required by Self. Called once when
+    /// the system is added to a schedule.
+    fn init_state(_state: &mut SystemState) {}
 }
 
-impl<'a, Q: Query> ComponentBorrow for Q {
+impl<Q: Query> ComponentBorrow for Q {
     fn borrows() -> Borrows {
         let mut borrows = SmallVec::with_capacity(8);
 
@@ -1,5 +1,7 @@
-///! This module works around the lifetimes for borrow when GAT isn't available
-use crate::{Read, SubWorld, Write};
+//! This module works around the lifetimes for borrow when GAT isn't available
+use std::marker::PhantomData;
+
+use crate::{filter::ArchetypeFilter, Read, SubWorld, Write};
 
 use super::{ContextBorrow, MaybeRead, MaybeWrite};
 
@@ -36,4 +38,18 @@ impl_into_borrow!(Component, Read => Borrower);
 impl_into_borrow!(Component, Write => BorrowMut);
 impl_into_borrow!(Component, MaybeRead => MaybeBorrower);
 impl_into_borrow!(Component, MaybeWrite => MaybeBorrowerMut);
-impl_into_borrow!(Component, SubWorld => SubWorldBorrower);
+
+#[doc(hidden)]
+pub struct SubWorldBorrower<T, F>(PhantomData<(T, F)>);
+
+impl<T: Component, F: ArchetypeFilter> IntoBorrow for SubWorld<'_, T, F> {
+    type Borrow = SubWorldBorrower<T, F>;
+}
+
+impl<'a, T: Component, F: ArchetypeFilter> ContextBorrow<'a> for SubWorldBorrower<T, F> {
+    type Target = SubWorld<'a, T, F>;
+
+    fn borrow(context: &'a crate::Context) -> crate::error::Result<Self::Target> {
+        Self::Target::borrow(context)
+    }
+}
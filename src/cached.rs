@@ -0,0 +1,135 @@
+use std::{any::type_name, marker::PhantomData};
+
+use atomic_refcell::{AtomicRef, AtomicRefMut};
+use hecs::{Component, PreparedQuery, PreparedQueryBorrow, Query, World};
+
+use crate::{
+    borrow::{Borrows, ComponentBorrow, ContextBorrow, IntoBorrow},
+    filter::ArchetypeFilter,
+    system::SystemState,
+    Access, Context, Error, IntoAccess, Result,
+};
+
+/// The prepared query of a [`CachedQuery`], kept in the system state
+struct Prepared<Q: Query>(PreparedQuery<Q>);
+
+impl<Q: Query> Default for Prepared<Q> {
+    fn default() -> Self {
+        Self(PreparedQuery::new())
+    }
+}
+
+// SAFETY: `PreparedQuery` is neither `Send` nor `Sync` because of its `fetch`
+// slots, which cache `Q::Fetch` values pointing into the component columns of
+// the world. These are only filled in by a `PreparedView`, which resets every
+// slot and executes the fetch of each matched archetype anew before reading,
+// while `PreparedQueryBorrow` holds the dynamic borrows of the components.
+// The pointers left behind once the view is dropped are thus stale, but never
+// dereferenced again: the next view overwrites them, and a change of the
+// archetypes prepares the query again, which discards them. The remaining
+// `state` is the plain data hecs computes per archetype, such as column
+// indices. The items themselves are only sent across threads as the iterators
+// of hecs do, hence the same `Send` bound. Finally, the system state only
+// hands out exclusive borrows of the prepared query, to one thread at a time.
+unsafe impl<Q: Query> Send for Prepared<Q> where for<'a> Q::Item<'a>: Send {}
+unsafe impl<Q: Query> Sync for Prepared<Q> where for<'a> Q::Item<'a>: Send {}
+
+/// A query which remembers the archetypes it matched between executions of
+/// the system, backed by a [`hecs::PreparedQuery`].
+///
+/// The archetypes are matched again when the archetypes of the world change,
+/// such as when an entity with a new set of components is spawned. Accesses
+/// `Q` like a [`SubWorld<Q, F>`](crate::SubWorld), and is restricted to the
+/// entities passing the [`ArchetypeFilter`] `F`.
+///
+/// A system may only have one `CachedQuery` of the same query and filter.
+/// Adding a system with more panics.
+pub struct CachedQuery<'a, Q: Query, F: ArchetypeFilter = ()> {
+    world: AtomicRef<'a, World>,
+    prepared: AtomicRefMut<'a, Prepared<F::Query<Q>>>,
+    marker: PhantomData<F>,
+}
+
+impl<'a, Q: Query, F: ArchetypeFilter> CachedQuery<'a, Q, F> {
+    /// Query the world, reusing the matched archetypes unless the archetypes
+    /// of the world changed
+    pub fn query(&mut self) -> PreparedQueryBorrow<'_, F::Query<Q>> {
+        self.prepared.0.query(&self.world)
+    }
+}
+
+impl<'a, Q, F> ContextBorrow<'a> for CachedQuery<'a, Q, F>
+where
+    Q: Query + Component,
+    F: ArchetypeFilter,
+    for<'x> <F::Query<Q> as Query>::Item<'x>: Send,
+{
+    type Target = Self;
+
+    fn borrow(context: &'a Context) -> Result<Self> {
+        let world = context
+            .cell::<&World>()?
+            .try_borrow()
+            .map_err(|_| Error::Borrow(type_name::<Q>()))
+            .map(|cell| AtomicRef::map(cell, |val| unsafe { val.cast().as_ref() }))?;
+
+        Ok(Self {
+            world,
+            prepared: context.state()?,
+            marker: PhantomData,
+        })
+    }
+}
+
+impl<Q, F> ComponentBorrow for CachedQuery<'_, Q, F>
+where
+    Q: Query + Component,
+    F: ArchetypeFilter,
+    for<'x> <F::Query<Q> as Query>::Item<'x>: Send,
+{
+    fn borrows() -> Borrows {
+        let mut access: Borrows = Q::borrows()
+            .into_iter()
+            .map(|access| access.filtered::<F>())
+            .collect();
+        access.push(Access::of::<&World>());
+        access
+    }
+
+    fn has<U: IntoAccess>() -> bool {
+        Q::has::<U>()
+    }
+
+    fn has_dynamic(id: std::any::TypeId, exclusive: bool) -> bool {
+        Q::has_dynamic(id, exclusive)
+    }
+
+    fn init_state(state: &mut SystemState) {
+        state.init_unique::<Prepared<F::Query<Q>>>()
+    }
+}
+
+#[doc(hidden)]
+pub struct CachedQueryBorrower<Q, F>(PhantomData<(Q, F)>);
+
+impl<Q, F> IntoBorrow for CachedQuery<'_, Q, F>
+where
+    Q: Query + Component,
+    F: ArchetypeFilter,
+    for<'x> <F::Query<Q> as Query>::Item<'x>: Send,
+{
+    type Borrow = CachedQueryBorrower<Q, F>;
+}
+
+impl<'a, Q, F> ContextBorrow<'a> for CachedQueryBorrower<Q, F>
+where
+    Q: Query + Component,
+    F: ArchetypeFilter,
+    for<'x> <F::Query<Q> as Query>::Item<'x>: Send,
+{
+    type Target = CachedQuery<'a, Q, F>;
+
+    fn borrow(context: &'a Context) -> Result<Self::Target> {
+        Self::Target::borrow(context)
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use hecs::{Component, Entity, Query, QueryBorrow};
+
+/// The global change tick. Zero precedes every tick, such that everything is
+/// new to a system which has not yet run.
+static CHANGE_TICK: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// A point in time used for change detection.
+///
+/// The tick is advanced by the [`Schedule`](crate::Schedule) before each batch
+/// of systems.
+pub struct Tick(pub(crate) u64);
+
+impl Tick {
+    /// Returns the current change tick
+    pub fn now() -> Self {
+        Self(CHANGE_TICK.load(Ordering::Acquire))
+    }
+
+    /// Advances the change tick and returns the new value
+    pub(crate) fn advance() -> Self {
+        Self(CHANGE_TICK.fetch_add(1, Ordering::AcqRel) + 1)
+    }
+}
+
+#[derive(Default, Debug)]
+/// Records when each resource was last mutably accessed through
+/// [`Write`](crate::Write).
+///
+/// Resources are provided anew for each execution, so the ticks are kept by
+/// the [`Schedule`](crate::Schedule). Each system resolves the ticks of the
+/// resources it borrows once, when first executed.
+pub struct ResourceTicks {
+    ticks: RwLock<HashMap<TypeId, Arc<AtomicU64>>>,
+}
+
+impl ResourceTicks {
+    /// Creates a new empty set of ticks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the tick of the resource with type `ty`. Resources which are
+    /// seen for the first time are considered changed.
+    pub(crate) fn get(&self, ty: TypeId) -> Arc<AtomicU64> {
+        if let Some(tick) = self.ticks.read().unwrap().get(&ty) {
+            return tick.clone();
+        }
+
+        self.ticks
+            .write()
+            .unwrap()
+            .entry(ty)
+            .or_insert_with(|| Arc::new(AtomicU64::new(Tick::now().0)))
+            .clone()
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+/// The ticks of the previous execution of a system, which change detection
+/// compares against
+pub struct SystemTicks {
+    /// The tick at which the system last ran
+    pub last_run: Tick,
+    /// The tick up to which commands were flushed into the world when the
+    /// system last ran. Values created after it, such as by a system recording
+    /// commands, may not yet have been inserted into the world.
+    pub last_flush: Tick,
+}
+
+impl SystemTicks {
+    /// Ticks comparing against `tick` for both fields
+    pub fn new(tick: Tick) -> Self {
+        Self {
+            last_run: tick,
+            last_flush: tick,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Wraps a component and records when it was added and last mutably accessed.
+///
+/// A new value is stamped with the current tick when created. Values created
+/// by a system and inserted through a
+/// [`CommandBuffer`](crate::CommandBuffer) only enter the world at the next
+/// flush, which the filters account for by comparing the added tick against
+/// [`SystemTicks::last_flush`].
+pub struct Tracked<T> {
+    value: T,
+    added: AtomicU64,
+    changed: AtomicU64,
+}
+
+impl<T> Tracked<T> {
+    /// Wraps a value
+    pub fn new(value: T) -> Self {
+        let now = Tick::now().0;
+        Self {
+            value,
+            added: AtomicU64::new(now),
+            changed: AtomicU64::new(now),
+        }
+    }
+
+    /// Returns the wrapped value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Returns true if the value was created after `since`
+    pub fn is_added(&self, since: Tick) -> bool {
+        Tick(self.added.load(Ordering::Acquire)) > since
+    }
+
+    /// Returns true if the value was created or mutably accessed after `since`
+    pub fn is_changed(&self, since: Tick) -> bool {
+        Tick(self.changed.load(Ordering::Acquire)) > since
+    }
+
+    /// Marks the value as changed
+    pub fn set_changed(&mut self) {
+        *self.changed.get_mut() = Tick::now().0
+    }
+
+    /// Mutably accesses the value without marking it as changed
+    pub fn bypass_change_detection(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: Clone> Clone for Tracked<T> {
+    /// Clones the value. The clone is considered new.
+    fn clone(&self) -> Self {
+        Self::new(self.value.clone())
+    }
+}
+
+impl<T: Default> Default for Tracked<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> From<T> for Tracked<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Tracked<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.set_changed();
+        &mut self.value
+    }
+}
+
+/// Filters entities in a query by comparing change ticks.
+///
+/// See [`SubWorldRaw::query_filtered`](crate::SubWorldRaw::query_filtered).
+pub trait Filter {
+    /// The components required to evaluate the filter
+    type Query: Query;
+
+    /// Returns true if the entity passes the filter
+    fn matches(item: &<Self::Query as Query>::Item<'_>, since: SystemTicks) -> bool;
+}
+
+/// Filter for entities whose [`Tracked<T>`] was added since the system last
+/// ran.
+///
+/// Values created before the system last ran, but inserted into the world by
+/// a later flush, are considered added.
+pub struct Added<T>(PhantomData<T>);
+
+/// Filter for entities whose [`Tracked<T>`] was added or mutably accessed
+/// since the system last ran
+pub struct Changed<T>(PhantomData<T>);
+
+impl<T: Component> Filter for Added<T> {
+    type Query = &'static Tracked<T>;
+
+    fn matches(item: &&Tracked<T>, since: SystemTicks) -> bool {
+        item.is_added(since.last_flush)
+    }
+}
+
+impl<T: Component> Filter for Changed<T> {
+    type Query = &'static Tracked<T>;
+
+    fn matches(item: &&Tracked<T>, since: SystemTicks) -> bool {
+        item.is_changed(since.last_run) || item.is_added(since.last_flush)
+    }
+}
+
+macro_rules! tuple_impl {
+    ($([$idx: tt => $name: ident]),*) => {
+        impl<$($name: Filter),*> Filter for ($($name,)*) {
+            type Query = ($($name::Query,)*);
+
+            fn matches(item: &<Self::Query as Query>::Item<'_>, since: SystemTicks) -> bool {
+                $($name::matches(&item.$idx, since))&&*
+            }
+        }
+    };
+}
+
+impl_for_tuples_idx!(tuple_impl);
+
+/// A query borrow which only yields the entities passing the filter `F`
+pub struct FilteredQuery<'w, Q: Query, F: Filter> {
+    borrow: QueryBorrow<'w, (Q, F::Query)>,
+    since: SystemTicks,
+}
+
+impl<'w, Q: Query, F: Filter> FilteredQuery<'w, Q, F> {
+    pub(crate) fn new(borrow: QueryBorrow<'w, (Q, F::Query)>, since: SystemTicks) -> Self {
+        Self { borrow, since }
+    }
+
+    /// Iterates the entities which pass the filter
+    pub fn iter(&mut self) -> impl Iterator<Item = (Entity, Q::Item<'_>)> {
+        let since = self.since;
+        self.borrow
+            .iter()
+            .filter(move |(_, (_, filter))| F::matches(filter, since))
+            .map(|(entity, (item, _))| (entity, item))
+    }
+}
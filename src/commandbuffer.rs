@@ -1,19 +1,230 @@
-use hecs::{
-    Bundle, CommandBuffer as CommandBufferInternal, Component, DynamicBundle, Entity, World,
+use std::{any::TypeId, fmt::Display, mem::size_of_val};
+
+use hecs::{Bundle, Component, ComponentError, DynamicBundle, Entity, EntityBuilder, World};
+use smallvec::{smallvec, SmallVec};
+
+use crate::{
+    error::{CommandError, CommandErrors},
+    hierarchy,
+    journal::Journal,
+    observer::{PendingTrigger, TriggerQueue},
+    removal, CloneRegistry, Error, GenericWorld, Hooks, Removals, SystemName, Trigger,
 };
 
+type WriteCommand = Box<dyn FnOnce(&mut World) + Send + Sync>;
+type SpawnBatchCommand = Box<dyn FnOnce(&mut World, &mut dyn FnMut(Entity)) + Send + Sync>;
+type InsertBatchCommand = Box<dyn FnOnce(&mut World) -> Vec<Entity> + Send + Sync>;
+pub(crate) type ComponentIds = SmallVec<[TypeId; 8]>;
+
+#[derive(Clone, Copy)]
+/// Type erased removal of a set of components
+pub(crate) struct RemoveCommand {
+    remove: fn(&mut World, Entity) -> std::result::Result<(), ComponentError>,
+    ids: fn() -> ComponentIds,
+}
+
+impl RemoveCommand {
+    /// Removes the bundle `C`
+    pub(crate) fn bundle<C: Component + Bundle>() -> Self {
+        Self {
+            remove: |world, entity| world.remove::<C>(entity).map(|_| ()),
+            ids: || C::with_static_ids(|ids| ids.iter().copied().collect()),
+        }
+    }
+
+    /// Removes the single component `C`
+    pub(crate) fn one<C: Component>() -> Self {
+        Self {
+            remove: |world, entity| world.remove_one::<C>(entity).map(|_| ()),
+            ids: || smallvec![TypeId::of::<C>()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Describes the kind of a recorded command
+pub enum CommandKind {
+    /// Spawn a new entity
+    Spawn,
+    /// Insert components into an existing or reserved entity
+    Insert,
+    /// Remove components from an entity
+    Remove,
+    /// Despawn an entity
+    Despawn,
+    /// A custom closure modifying the world
+    Write,
+}
+
+pub(crate) enum Command {
+    Spawn(EntityBuilder),
+    SpawnAt(Entity, EntityBuilder),
+    SpawnBatch(usize, SpawnBatchCommand),
+    Insert(Entity, EntityBuilder),
+    InsertBatch(Vec<Entity>, ComponentIds, InsertBatchCommand),
+    Remove(Entity, RemoveCommand),
+    Despawn(Entity),
+    DespawnRecursive(Entity),
+    SetParent(Entity, Option<Entity>),
+    Trigger(PendingTrigger),
+    Write(WriteCommand),
+}
+
+impl Command {
+    /// Returns the entities which may lose components by the command
+    fn removal_targets(&self, world: &World) -> Vec<Entity> {
+        match self {
+            Command::SpawnAt(entity, _) | Command::Remove(entity, _) | Command::Despawn(entity) => {
+                vec![*entity]
+            }
+            Command::DespawnRecursive(entity) => hierarchy::subtree(world, *entity),
+            Command::SetParent(child, _) => vec![*child],
+            // A custom write may edit any entity
+            Command::Write(_) => world.iter().map(|e| e.entity()).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the components which will be removed by the command
+    fn removing(&self, world: &World) -> Vec<(Entity, TypeId)> {
+        let all = |entity: Entity| {
+            removal::component_types(world, entity)
+                .into_iter()
+                .map(move |ty| (entity, ty))
+        };
+
+        match self {
+            Command::SpawnAt(entity, _) | Command::Despawn(entity) => all(*entity).collect(),
+            Command::DespawnRecursive(entity) => hierarchy::subtree(world, *entity)
+                .into_iter()
+                .flat_map(all)
+                .collect(),
+            // Removing a bundle fails unless every component is present
+            Command::Remove(entity, remove) => {
+                let current = removal::component_types(world, *entity);
+                let ids = (remove.ids)();
+                if ids.iter().all(|ty| current.contains(ty)) {
+                    ids.into_iter().map(|ty| (*entity, ty)).collect()
+                } else {
+                    Vec::new()
+                }
+            }
+            Command::SetParent(child, None) => all(*child)
+                .filter(|&(_, ty)| ty == TypeId::of::<hierarchy::Parent>())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the existing entities which may receive components by the
+    /// command, along with their current components and the components which
+    /// are explicitly inserted
+    fn inserting(&self, world: &World) -> Vec<(Entity, ComponentIds, ComponentIds)> {
+        let current = |entity| removal::component_types(world, entity);
+        match self {
+            Command::Insert(entity, builder) => {
+                vec![(
+                    *entity,
+                    current(*entity),
+                    builder.component_types().collect(),
+                )]
+            }
+            Command::SpawnAt(entity, builder) => {
+                vec![(
+                    *entity,
+                    ComponentIds::new(),
+                    builder.component_types().collect(),
+                )]
+            }
+            Command::InsertBatch(entities, ids, _) => entities
+                .iter()
+                .map(|&entity| (entity, current(entity), ids.clone()))
+                .collect(),
+            Command::SetParent(child, Some(parent)) => vec![
+                (
+                    *child,
+                    current(*child),
+                    smallvec![TypeId::of::<hierarchy::Parent>()],
+                ),
+                (*parent, current(*parent), ComponentIds::new()),
+            ],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the entities targeted by the command
+    fn targets(&self) -> &[Entity] {
+        match self {
+            Command::SpawnAt(entity, _)
+            | Command::Insert(entity, _)
+            | Command::Remove(entity, _)
+            | Command::Despawn(entity)
+            | Command::DespawnRecursive(entity)
+            | Command::SetParent(entity, _) => std::slice::from_ref(entity),
+            Command::InsertBatch(entities, ..) => entities,
+            Command::Trigger(trigger) => trigger.entity.as_slice(),
+            Command::Spawn(_) | Command::SpawnBatch(..) | Command::Write(_) => &[],
+        }
+    }
+}
+
+struct RecordedCommand {
+    command: Command,
+    system: Option<SystemName>,
+    /// Size of the data owned by the command
+    size: usize,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+/// Describes the pending work of a [`CommandBuffer`]. Batches count each of
+/// their entities.
+pub struct CommandStats {
+    /// Number of entities to spawn
+    pub spawns: usize,
+    /// Number of component insertions
+    pub inserts: usize,
+    /// Number of component removals
+    pub removes: usize,
+    /// Number of entities to despawn
+    pub despawns: usize,
+    /// Number of custom writes
+    pub writes: usize,
+    /// Number of triggers sent to observers
+    pub triggers: usize,
+    /// Approximate number of bytes used by the recorded commands, excluding
+    /// heap memory owned by components or closures
+    pub memory: usize,
+}
+
+impl Display for CommandStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "spawns: {}, inserts: {}, removes: {}, despawns: {}, writes: {}, triggers: {}, memory: {} B",
+            self.spawns,
+            self.inserts,
+            self.removes,
+            self.despawns,
+            self.writes,
+            self.triggers,
+            self.memory
+        )
+    }
+}
+
 #[derive(Default)]
 /// Extends the built in [hecs::CommandBuffer].
 ///
 /// Allows for deferred modifications to the world, spawn, insert, remove,
 /// despawn, or custom closures.
 ///
+/// Commands are applied in the order they were recorded.
+///
 /// It is possible to insert a commandbuffer into another commandbuffer.
 pub struct CommandBuffer {
-    /// Use the already existing hecs::CommmandBuffer
-    components: CommandBufferInternal,
-    despawns: Vec<Entity>,
-    writes: Vec<Box<dyn FnOnce(&mut World) + Send + Sync>>,
+    commands: Vec<RecordedCommand>,
+    system: Option<SystemName>,
 }
 
 impl CommandBuffer {
@@ -22,67 +233,470 @@ impl CommandBuffer {
         Self::default()
     }
 
+    pub(crate) fn push(&mut self, command: Command, size: usize) {
+        self.commands.push(RecordedCommand {
+            command,
+            system: self.system.clone(),
+            size,
+        })
+    }
+
+    /// Attributes all subsequently recorded commands to `system`. The name is
+    /// reported in [`CommandError`] if the command fails to apply.
+    pub fn set_system(&mut self, system: impl Into<SystemName>) {
+        self.system = Some(system.into())
+    }
+
+    /// Stops attributing recorded commands to a system
+    pub fn clear_system(&mut self) {
+        self.system = None
+    }
+
     /// Inserts components into an already existing or reserved entity
     pub fn insert(&mut self, entity: Entity, components: impl DynamicBundle) {
-        self.components.insert(entity, components)
+        let size = size_of_val(&components);
+        let mut builder = EntityBuilder::new();
+        builder.add_bundle(components);
+        self.push(Command::Insert(entity, builder), size)
     }
 
     /// Inserts a single component into an already existing or reserved entity
     pub fn insert_one(&mut self, entity: Entity, component: impl Component) {
-        self.components.insert(entity, (component,))
+        self.insert(entity, (component,))
     }
 
     /// Spawns a new entity with components.
-    /// If the entity ID is desired, consider reserving an entity and then inserting
+    /// If the entity ID is desired, use [`Self::spawn_reserved`]
     pub fn spawn(&mut self, components: impl DynamicBundle) {
-        self.components.spawn(components)
+        let size = size_of_val(&components);
+        let mut builder = EntityBuilder::new();
+        builder.add_bundle(components);
+        self.push(Command::Spawn(builder), size)
+    }
+
+    /// Reserves an entity from `world` and spawns it with components when the
+    /// commandbuffer is executed.
+    ///
+    /// The returned entity can immediately be referenced by other commands or
+    /// components, such as when parenting.
+    pub fn spawn_reserved(
+        &mut self,
+        world: &impl GenericWorld,
+        components: impl DynamicBundle,
+    ) -> Entity {
+        let entity = world.reserve();
+        self.insert(entity, components);
+        entity
+    }
+
+    /// Spawns an entity with components using a specific handle, such as a
+    /// reserved entity or an entity from another world.
+    ///
+    /// Any existing entity with the same id is despawned.
+    pub fn spawn_at(&mut self, entity: Entity, components: impl DynamicBundle) {
+        let size = size_of_val(&components);
+        let mut builder = EntityBuilder::new();
+        builder.add_bundle(components);
+        self.push(Command::SpawnAt(entity, builder), size)
+    }
+
+    /// Spawns many entities with the same set of components.
+    ///
+    /// The entities are spawned using [`World::spawn_batch`], which is faster
+    /// than spawning them one at a time.
+    pub fn spawn_batch<I>(&mut self, iter: I)
+    where
+        I: IntoIterator,
+        I::Item: Bundle + Component,
+    {
+        let batch: Vec<_> = iter.into_iter().collect();
+        let (count, size) = (batch.len(), size_of_val(batch.as_slice()));
+        self.push(
+            Command::SpawnBatch(
+                count,
+                Box::new(move |world, on_spawn| world.spawn_batch(batch).for_each(on_spawn)),
+            ),
+            size,
+        )
+    }
+
+    /// Inserts components into many already existing or reserved entities
+    pub fn insert_batch<I, B>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (Entity, B)>,
+        B: Bundle + Component,
+    {
+        let batch: Vec<_> = iter.into_iter().collect();
+        let entities: Vec<_> = batch.iter().map(|&(entity, _)| entity).collect();
+        let size = size_of_val(batch.as_slice()) + size_of_val(entities.as_slice());
+        self.push(
+            Command::InsertBatch(
+                entities,
+                B::with_static_ids(|ids| ids.iter().copied().collect()),
+                Box::new(move |world| {
+                    batch
+                        .into_iter()
+                        .filter_map(|(entity, components)| {
+                            world.insert(entity, components).err().map(|_| entity)
+                        })
+                        .collect()
+                }),
+            ),
+            size,
+        )
     }
 
     /// Despawn an entity from the world
     pub fn despawn(&mut self, entity: Entity) {
-        self.despawns.push(entity)
+        self.push(Command::Despawn(entity), 0)
+    }
+
+    /// Despawn an entity and all its descendants, as given by
+    /// [`Children`](crate::hierarchy::Children), from the world.
+    ///
+    /// The entity is also removed from the children of its parent.
+    pub fn despawn_recursive(&mut self, entity: Entity) {
+        self.push(Command::DespawnRecursive(entity), 0)
+    }
+
+    /// Sets the parent of `child`, updating the [`Parent`](crate::hierarchy::Parent)
+    /// and [`Children`](crate::hierarchy::Children) components of the entities
+    /// involved.
+    ///
+    /// Fails to apply if either entity does not exist or if `parent` is a
+    /// descendant of `child`.
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) {
+        self.push(Command::SetParent(child, Some(parent)), 0)
+    }
+
+    /// Detaches `child` from its parent, if any, making it a root.
+    pub fn remove_parent(&mut self, child: Entity) {
+        self.push(Command::SetParent(child, None), 0)
+    }
+
+    /// Sends `event` to the observers of `E` when the commandbuffer is flushed
+    /// by a schedule. See [`ScheduleBuilder::observe`](crate::ScheduleBuilder::observe).
+    pub fn trigger<E: Trigger>(&mut self, event: E) {
+        let size = size_of_val(&event);
+        self.push(Command::Trigger(PendingTrigger::new(None, event)), size)
+    }
+
+    /// Sends `event`, targeting `entity`, to the observers of `E` when the
+    /// commandbuffer is flushed by a schedule.
+    pub fn trigger_for<E: Trigger>(&mut self, entity: Entity, event: E) {
+        let size = size_of_val(&event);
+        self.push(
+            Command::Trigger(PendingTrigger::new(Some(entity), event)),
+            size,
+        )
     }
 
     /// Remove components from entity
     pub fn remove<C: Component + Bundle>(&mut self, entity: Entity) {
-        self.writes.push(Box::new(move |w| {
-            let _ = w.remove::<C>(entity);
-        }))
+        self.push(Command::Remove(entity, RemoveCommand::bundle::<C>()), 0)
     }
 
     /// Remove a single component from the world
     pub fn remove_one<C: Component>(&mut self, entity: Entity) {
-        self.writes.push(Box::new(move |w| {
-            let _ = w.remove_one::<C>(entity);
-        }))
+        self.push(Command::Remove(entity, RemoveCommand::one::<C>()), 0)
+    }
+
+    /// Remove components from entity using a type erased remove function
+    #[cfg(feature = "serde")]
+    pub(crate) fn remove_dynamic(&mut self, entity: Entity, remove: RemoveCommand) {
+        self.push(Command::Remove(entity, remove), 0)
     }
 
-    /// Applies the recorded commands on the world
+    /// Applies the recorded commands on the world.
+    ///
+    /// Commands which fail to apply, such as despawning a non existent
+    /// entity, are ignored. Use [`Self::try_execute`] to observe failures.
     pub fn execute(&mut self, world: &mut World) {
-        self.components.run_on(world);
+        let _ = self.try_execute(world);
+    }
+
+    /// Applies the recorded commands on the world.
+    ///
+    /// Every command is applied, even if a previous command failed. Returns all
+    /// commands which failed to apply.
+    pub fn try_execute(&mut self, world: &mut World) -> Result<(), CommandErrors> {
+        self.apply(world, None, None, None, None)
+    }
+
+    /// Applies the recorded commands like [`Self::try_execute`], and runs the
+    /// lifecycle hooks of the inserted and removed components.
+    pub fn try_execute_with_hooks(
+        &mut self,
+        world: &mut World,
+        hooks: &Hooks,
+    ) -> Result<(), CommandErrors> {
+        self.apply(world, None, None, Some(hooks), None)
+    }
+
+    /// Applies the recorded commands like [`Self::try_execute`], and records
+    /// the removed components and despawned entities in `removals`.
+    ///
+    /// Removals performed by custom writes are recorded by comparing the
+    /// components of every entity before and after the write.
+    pub fn try_execute_recording(
+        &mut self,
+        world: &mut World,
+        removals: &Removals,
+    ) -> Result<(), CommandErrors> {
+        self.apply(world, None, Some(removals), None, None)
+    }
+
+    /// Applies the recorded commands as a flush of a schedule
+    pub(crate) fn flush(
+        &mut self,
+        world: &mut World,
+        removals: Option<&Removals>,
+        hooks: Option<&Hooks>,
+        triggers: Option<&TriggerQueue>,
+    ) -> Result<(), CommandErrors> {
+        self.apply(world, None, removals, hooks, triggers)
+    }
+
+    /// Applies the recorded commands on the world, and returns a commandbuffer
+    /// which reverts the changes when executed.
+    ///
+    /// Removed, overwritten and despawned components are captured by cloning
+    /// the components registered in `registry`. Components which are not
+    /// registered can not be restored, and custom writes are not reverted.
+    ///
+    /// Executing the inverse in the same manner yields a commandbuffer which
+    /// redoes the changes.
+    ///
+    /// Like [`Self::execute`], commands which fail to apply are ignored.
+    pub fn execute_with_inverse(
+        &mut self,
+        world: &mut World,
+        registry: &CloneRegistry,
+    ) -> CommandBuffer {
+        let mut journal = Journal::new(registry);
+        let _ = self.apply(world, Some(&mut journal), None, None, None);
+        journal.finish()
+    }
+
+    fn apply(
+        &mut self,
+        world: &mut World,
+        mut journal: Option<&mut Journal>,
+        removals: Option<&Removals>,
+        hooks: Option<&Hooks>,
+        triggers: Option<&TriggerQueue>,
+    ) -> Result<(), CommandErrors> {
+        let hooks = hooks.filter(|hooks| !hooks.is_empty());
+        let mut errors = Vec::new();
+        let mut fail = |entity, kind, system: &Option<SystemName>, error| {
+            errors.push(CommandError {
+                entity,
+                kind,
+                system: system.clone(),
+                error,
+            })
+        };
+
+        for cmd in self.commands.drain(..) {
+            let system = &cmd.system;
+
+            let snapshots = journal.as_deref_mut().map(|journal| {
+                let targets = match &cmd.command {
+                    Command::DespawnRecursive(entity) => {
+                        hierarchy::despawn_recursive_targets(world, *entity)
+                    }
+                    Command::SetParent(child, parent) => {
+                        hierarchy::set_parent_targets(world, *child, *parent)
+                    }
+                    command => command.targets().to_vec(),
+                };
+
+                journal.snapshot(world, &targets)
+            });
+            let removed = removals.map(|_| {
+                world.flush();
+                removal::snapshot(world, &cmd.command.removal_targets(world))
+            });
+            let inserting = hooks.map(|hooks| {
+                world.flush();
+                hooks.removing(world, &cmd.command.removing(world));
+                cmd.command.inserting(world)
+            });
+            let mut spawned = Vec::new();
+
+            match cmd.command {
+                Command::Spawn(mut builder) => {
+                    spawned.push(world.spawn(builder.build()));
+                }
+                Command::SpawnAt(entity, mut builder) => {
+                    world.spawn_at(entity, builder.build());
+                }
+                Command::SpawnBatch(_, spawn) => {
+                    let track = journal.is_some() || hooks.is_some();
+                    (spawn)(world, &mut |entity| {
+                        if track {
+                            spawned.push(entity)
+                        }
+                    })
+                }
+                Command::Insert(entity, mut builder) => {
+                    if world.insert(entity, builder.build()).is_err() {
+                        fail(
+                            entity,
+                            CommandKind::Insert,
+                            system,
+                            Error::NoSuchEntity(entity),
+                        )
+                    }
+                }
+                Command::InsertBatch(_, _, insert) => {
+                    for entity in (insert)(world) {
+                        fail(
+                            entity,
+                            CommandKind::Insert,
+                            system,
+                            Error::NoSuchEntity(entity),
+                        )
+                    }
+                }
+                Command::Remove(entity, remove) => match (remove.remove)(world, entity) {
+                    Ok(()) => {}
+                    Err(ComponentError::NoSuchEntity) => fail(
+                        entity,
+                        CommandKind::Remove,
+                        system,
+                        Error::NoSuchEntity(entity),
+                    ),
+                    Err(ComponentError::MissingComponent(name)) => fail(
+                        entity,
+                        CommandKind::Remove,
+                        system,
+                        Error::MissingComponent(entity, name),
+                    ),
+                },
+                Command::Despawn(entity) => {
+                    if world.despawn(entity).is_err() {
+                        fail(
+                            entity,
+                            CommandKind::Despawn,
+                            system,
+                            Error::NoSuchEntity(entity),
+                        )
+                    }
+                }
+                Command::DespawnRecursive(entity) => {
+                    if let Err(error) = hierarchy::despawn_recursive(world, entity) {
+                        fail(entity, CommandKind::Despawn, system, error)
+                    }
+                }
+                Command::SetParent(child, parent) => {
+                    if let Err(error) = hierarchy::set_parent(world, child, parent) {
+                        let kind = match parent {
+                            Some(_) => CommandKind::Insert,
+                            None => CommandKind::Remove,
+                        };
+                        fail(child, kind, system, error)
+                    }
+                }
+                Command::Trigger(trigger) => {
+                    if let Some(triggers) = triggers {
+                        triggers.push_pending(trigger)
+                    }
+                }
+                Command::Write(write) => (write)(world),
+            }
+
+            if let (Some(hooks), Some(inserting)) = (hooks, inserting) {
+                for (entity, before, explicit) in inserting {
+                    hooks.inserted(world, entity, &before, &explicit);
+                }
+
+                for &entity in &spawned {
+                    hooks.inserted(world, entity, &[], &[]);
+                }
+            }
+
+            if let (Some(journal), Some(snapshots)) = (journal.as_deref_mut(), snapshots) {
+                journal.record(world, snapshots, spawned);
+            }
 
-        self.writes.drain(..).for_each(|cmd| (cmd)(world));
+            if let (Some(removals), Some(removed)) = (removals, removed) {
+                removals.record_snapshot(world, removed);
+            }
+        }
 
-        self.despawns
-            .drain(..)
-            .for_each(|e| world.despawn(e).expect("Failed to despawn entity"));
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(CommandErrors(errors))
+        }
     }
 
     /// Nest a commandbuffer
     pub fn append(&mut self, mut other: Self) {
-        self.write(move |w| other.execute(w))
+        self.commands.append(&mut other.commands)
     }
 
-    /// Record a custom command modifying the world
+    /// Record a custom command modifying the world.
+    ///
+    /// When the removals are recorded, the components of every entity are
+    /// compared around the write. A [`Schedule`](crate::Schedule) only records
+    /// them when any of its systems reads
+    /// [`RemovedComponents`](crate::RemovedComponents).
     pub fn write(&mut self, cmd: impl FnOnce(&mut World) + Component) {
-        self.writes.push(Box::new(cmd))
+        let size = size_of_val(&cmd);
+        self.push(Command::Write(Box::new(cmd)), size)
     }
 
     /// Drop all recorded commands
     pub fn clear(&mut self) {
-        self.despawns.clear();
-        self.writes.clear();
-        self.components.clear();
+        self.commands.clear();
     }
-}
 
+    /// Returns the number of recorded commands. A batch counts as a single
+    /// command.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Returns true if no commands are recorded
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Returns statistics of the pending commands
+    pub fn stats(&self) -> CommandStats {
+        let mut stats = CommandStats {
+            memory: self.commands.capacity() * std::mem::size_of::<RecordedCommand>(),
+            ..Default::default()
+        };
+
+        for cmd in &self.commands {
+            stats.memory += cmd.size;
+            match &cmd.command {
+                Command::Spawn(_) | Command::SpawnAt(..) => stats.spawns += 1,
+                Command::SpawnBatch(count, _) => stats.spawns += count,
+                Command::Insert(..) => stats.inserts += 1,
+                Command::InsertBatch(entities, ..) => stats.inserts += entities.len(),
+                Command::SetParent(_, Some(_)) => stats.inserts += 1,
+                Command::Remove(..) | Command::SetParent(_, None) => stats.removes += 1,
+                Command::Despawn(_) | Command::DespawnRecursive(_) => stats.despawns += 1,
+                Command::Write(_) => stats.writes += 1,
+                Command::Trigger(_) => stats.triggers += 1,
+            }
+        }
+
+        stats
+    }
+
+    /// Returns the entities which are targeted by the recorded commands, in
+    /// order. An entity may be returned more than once.
+    ///
+    /// Entities spawned without a handle and entities modified in custom
+    /// writes are not known, and thus not included.
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.commands
+            .iter()
+            .flat_map(|cmd| cmd.command.targets().iter().copied())
+    }
+}
@@ -28,7 +28,7 @@ impl Ord for ErasedCell {
 
 impl PartialOrd for ErasedCell {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.id.partial_cmp(&other.id)
+        Some(self.cmp(other))
     }
 }
 
@@ -1,16 +1,29 @@
 //! This module provides types and traits associated to accessing of borrowed
 //! values.
-use std::{any::TypeId, cmp::Ordering, ptr::NonNull};
+use std::{any::TypeId, cmp::Ordering, ptr::NonNull, sync::atomic::AtomicU64};
 
 use atomic_refcell::AtomicRefCell;
 
-use crate::{borrow::ContextBorrow, Error, IntoAccess, Result};
+use crate::{
+    borrow::ContextBorrow,
+    observer::{PendingTrigger, TriggerQueue},
+    system::SystemState,
+    Error, Hooks, IntoAccess, Removals, ResourceTicks, Result, SystemTicks, Tick,
+};
+use atomic_refcell::AtomicRefMut;
 use hecs::Component;
 
 /// Holds all data necessary for the execution of the world.
 /// The data is held by references, and needs to outlive the context itself
 pub struct Context<'a> {
     data: &'a dyn Data,
+    state: Option<&'a SystemState>,
+    ticks: Option<&'a ResourceTicks>,
+    removals: Option<&'a Removals>,
+    hooks: Option<&'a Hooks>,
+    triggers: Option<&'a TriggerQueue>,
+    trigger: Option<&'a PendingTrigger>,
+    flushed: Tick,
 }
 
 // Safe since Send + Sync is required for impl of IntoData
@@ -22,8 +35,93 @@ use erased_cell::*;
 
 impl<'a> Context<'a> {
     /// Construct a new context from the tuple of references `data`
-    pub fn new(data: &'a dyn Data) -> Context {
-        Self { data }
+    pub fn new(data: &'a dyn Data) -> Context<'a> {
+        Self {
+            data,
+            state: None,
+            ticks: None,
+            removals: None,
+            hooks: None,
+            triggers: None,
+            trigger: None,
+            flushed: Tick::default(),
+        }
+    }
+
+    /// Tracks changes of resources accessed through [`Write`](crate::Write)
+    /// in `ticks`
+    pub fn with_resource_ticks(mut self, ticks: &'a ResourceTicks) -> Self {
+        self.ticks = Some(ticks);
+        self
+    }
+
+    /// Records the removals of commandbuffer flushes in `removals`, and makes
+    /// them available to [`RemovedComponents`](crate::RemovedComponents)
+    pub fn with_removals(mut self, removals: &'a Removals) -> Self {
+        self.removals = Some(removals);
+        self
+    }
+
+    /// Returns the removal log, if any
+    pub fn removals(&self) -> Option<&'a Removals> {
+        self.removals
+    }
+
+    /// Runs the lifecycle `hooks` during commandbuffer flushes
+    pub fn with_hooks(mut self, hooks: &'a Hooks) -> Self {
+        self.hooks = Some(hooks);
+        self
+    }
+
+    /// Returns the lifecycle hooks, if any
+    pub fn hooks(&self) -> Option<&'a Hooks> {
+        self.hooks
+    }
+
+    /// Returns a context with the same data which provides the state of the
+    /// currently executing system
+    pub fn with_state<'b>(&'b self, state: &'b SystemState) -> Context<'b> {
+        Context {
+            data: self.data,
+            state: Some(state),
+            ticks: self.ticks,
+            removals: self.removals,
+            hooks: self.hooks,
+            triggers: self.triggers,
+            trigger: self.trigger,
+            flushed: self.flushed,
+        }
+    }
+
+    /// Returns a context with the same data which provides the trigger of the
+    /// currently executing observer
+    pub(crate) fn with_trigger<'b>(&'b self, trigger: &'b PendingTrigger) -> Context<'b> {
+        Context {
+            data: self.data,
+            state: self.state,
+            ticks: self.ticks,
+            removals: self.removals,
+            hooks: self.hooks,
+            triggers: self.triggers,
+            trigger: Some(trigger),
+            flushed: self.flushed,
+        }
+    }
+
+    /// Returns the trigger of the currently executing observer, if any
+    pub(crate) fn trigger(&self) -> Option<&'a PendingTrigger> {
+        self.trigger
+    }
+
+    /// Sends the triggers of commandbuffer flushes to `triggers`
+    pub(crate) fn with_triggers(mut self, triggers: &'a TriggerQueue) -> Self {
+        self.triggers = Some(triggers);
+        self
+    }
+
+    /// Returns the queue of triggers sent by commandbuffer flushes, if any
+    pub(crate) fn triggers(&self) -> Option<&'a TriggerQueue> {
+        self.triggers
     }
 
     /// Borrows data of type T from the context. Does not panic.
@@ -36,12 +134,62 @@ impl<'a> Context<'a> {
 
     /// Returns the cell associated to `T`.
     /// **Note**: Types are erased, but casting is guaranteed to be correct.
-    pub fn cell<T: IntoAccess>(&'a self) -> Result<&AtomicRefCell<NonNull<u8>>> {
+    pub fn cell<T: IntoAccess>(&'a self) -> Result<&'a AtomicRefCell<NonNull<u8>>> {
         let access = T::access();
         self.data
             .get(access.id())
             .ok_or_else(|| Error::MissingData(access.name()))
     }
+
+    /// Returns the change ticks of the resources, if tracked
+    pub(crate) fn resource_ticks(&self) -> Option<&'a ResourceTicks> {
+        self.ticks
+    }
+
+    /// Returns the change tick of the resource with type `ty`, if tracked for
+    /// the currently executing system
+    pub(crate) fn resource_tick(&self, ty: TypeId) -> Option<&'a AtomicU64> {
+        self.state?.resource_tick(ty)
+    }
+
+    /// Returns the tick at which the currently executing system last ran
+    pub fn last_run(&self) -> Tick {
+        self.state.map(SystemState::last_run).unwrap_or_default()
+    }
+
+    /// Returns the ticks of the previous execution of the currently executing
+    /// system
+    pub fn ticks(&self) -> SystemTicks {
+        self.state.map(SystemState::ticks).unwrap_or_default()
+    }
+
+    /// Returns a context with the same data, where commands created up to
+    /// `flushed` have been flushed into the world
+    pub(crate) fn with_flushed(&self, flushed: Tick) -> Context<'a> {
+        Context {
+            data: self.data,
+            state: self.state,
+            ticks: self.ticks,
+            removals: self.removals,
+            hooks: self.hooks,
+            triggers: self.triggers,
+            trigger: self.trigger,
+            flushed,
+        }
+    }
+
+    /// Returns the tick up to which commands have been flushed into the world
+    pub(crate) fn flushed(&self) -> Tick {
+        self.flushed
+    }
+
+    /// Exclusively borrows the value of type `S` from the state of the
+    /// currently executing system.
+    pub fn state<S: 'static>(&'a self) -> Result<AtomicRefMut<'a, S>> {
+        self.state
+            .ok_or(Error::MissingState(std::any::type_name::<S>()))?
+            .get()
+    }
 }
 
 /// Dynamically accessed static collection of values
@@ -0,0 +1,276 @@
+use std::{
+    any::{type_name, TypeId},
+    ops::Deref,
+};
+
+use atomic_refcell::AtomicRef;
+use hecs::{Archetype, Component, Entity, Fetch, Query, QueryBorrow, World};
+use smallvec::{smallvec, SmallVec};
+
+use crate::{
+    borrow::{Borrows, ComponentBorrow, ContextBorrow, IntoBorrow},
+    Access, Context, DynamicSystem, Error, IntoAccess, Result, ScheduleBuilder, System,
+};
+
+/// Type alias for a dynamic subworld referencing the world by an
+/// [atomic_refcell::AtomicRef], as used by [Schedule](crate::Schedule)
+pub type DynamicSubWorld<'a> = DynamicSubWorldRaw<AtomicRef<'a, World>>;
+/// Type alias for a dynamic subworld referencing the world by a reference
+pub type DynamicSubWorldRef<'a> = DynamicSubWorldRaw<&'a World>;
+
+/// The component accesses of a system added by
+/// [`ScheduleBuilder::add_dynamic_system`], kept in its system state
+pub(crate) struct DynamicAccess(pub(crate) Borrows);
+
+/// A subworld whose component accesses are only known at runtime, such as
+/// systems defined by a scripting layer.
+///
+/// The accesses are declared when adding the system through
+/// [`ScheduleBuilder::add_dynamic_system`], and take part in batching like the
+/// accesses of a [`SubWorld`](crate::SubWorld). Each access may be filtered,
+/// see [`Access::filtered`].
+///
+/// Every accessor is checked against the accesses at runtime.
+pub struct DynamicSubWorldRaw<A> {
+    world: A,
+    access: Borrows,
+}
+
+impl<A> DynamicSubWorldRaw<A> {
+    /// Creates a subworld which can only perform `access`. No borrow checking
+    /// is performed so may fail during query unless guarded otherwise.
+    pub fn new(world: A, access: impl IntoIterator<Item = Access>) -> Self {
+        Self {
+            world,
+            access: access.into_iter().collect(),
+        }
+    }
+
+    /// Returns the accesses of the subworld
+    pub fn access(&self) -> &[Access] {
+        &self.access
+    }
+
+    /// Returns true if the subworld can access the component `id`, for
+    /// entities passing the filter of the access
+    pub fn has_dynamic(&self, id: TypeId, exclusive: bool) -> bool {
+        self.access
+            .iter()
+            .any(|access| access.id == id && (access.exclusive || !exclusive))
+    }
+
+    /// Returns true if the subworld can access the borrow of T
+    pub fn has<U: IntoAccess>(&self) -> bool {
+        let access = U::access();
+        self.has_dynamic(access.id, access.exclusive)
+    }
+
+    /// Returns true if the component `id` of an entity with the components
+    /// given by `has` may be accessed
+    fn allows(&self, id: TypeId, exclusive: bool, has: impl Fn(TypeId) -> bool) -> bool {
+        self.access.iter().any(|access| {
+            access.id == id
+                && (access.exclusive || !exclusive)
+                && access.with().all(&has)
+                && !access.without().any(&has)
+        })
+    }
+
+    fn incompatible<Q>(&self) -> Error {
+        Error::IncompatibleSubworld {
+            subworld: type_name::<Self>(),
+            query: type_name::<Q>(),
+        }
+    }
+}
+
+impl<A: Deref<Target = World>> DynamicSubWorldRaw<A> {
+    /// Query the subworld.
+    /// # Panics
+    /// Panics if the query is not allowed by the accesses of the subworld.
+    pub fn query<Q: Query>(&self) -> QueryBorrow<'_, Q> {
+        self.try_query()
+            .expect("Failed to execute query on subworld")
+    }
+
+    /// Query the subworld.
+    ///
+    /// Fails if a component of `Q` is not accessible, or if `Q` matches
+    /// entities which do not pass the filter of the accesses.
+    pub fn try_query<Q: Query>(&self) -> Result<QueryBorrow<'_, Q>> {
+        let mut allowed = true;
+        Q::Fetch::for_each_borrow(|id, exclusive| allowed &= self.has_dynamic(id, exclusive));
+
+        for archetype in self.archetypes().filter(|arch| arch.satisfies::<Q>()) {
+            Q::Fetch::for_each_borrow(|id, exclusive| {
+                allowed &= !archetype.has_dynamic(id)
+                    || self.allows(id, exclusive, |id| archetype.has_dynamic(id))
+            });
+        }
+
+        if !allowed {
+            return Err(self.incompatible::<Q>());
+        }
+
+        Ok(self.world.query())
+    }
+
+    /// Iterates the entities with all the components `ids`, which need to be
+    /// accessible. Entities which do not pass the filter of the accesses are
+    /// skipped.
+    ///
+    /// The components are accessed through the typed accessors, such as
+    /// [`Self::get`].
+    pub fn query_dynamic(&self, ids: &[TypeId]) -> Result<impl Iterator<Item = Entity> + '_> {
+        if !ids.iter().all(|&id| self.has_dynamic(id, false)) {
+            return Err(Error::IncompatibleSubworld {
+                subworld: type_name::<Self>(),
+                query: "dynamic query",
+            });
+        }
+
+        let ids: SmallVec<[TypeId; 8]> = ids.into();
+        let world = self.world.deref();
+
+        Ok(self
+            .archetypes()
+            .filter(move |arch| {
+                ids.iter().all(|&id| {
+                    arch.has_dynamic(id) && self.allows(id, false, |id| arch.has_dynamic(id))
+                })
+            })
+            .flat_map(|arch| arch.ids())
+            // SAFETY: the ids of an archetype are of live entities, which can
+            // not be despawned while the world is borrowed
+            .map(move |&id| unsafe { world.find_entity_from_id(id) }))
+    }
+
+    /// Get a single component from the world.
+    ///
+    /// Wraps the hecs::NoSuchEntity error and provides the entity id
+    pub fn get<C: Component>(&self, entity: Entity) -> Result<hecs::Ref<'_, C>> {
+        self.check_entity::<&C>(entity)?;
+
+        match self.world.get::<&C>(entity) {
+            Ok(val) => Ok(val),
+            Err(hecs::ComponentError::NoSuchEntity) => Err(Error::NoSuchEntity(entity)),
+            Err(hecs::ComponentError::MissingComponent(name)) => {
+                Err(Error::MissingComponent(entity, name))
+            }
+        }
+    }
+
+    /// Get a single component mutably from the world. Requires exclusive
+    /// access to the component.
+    ///
+    /// Wraps the hecs::NoSuchEntity error and provides the entity id
+    pub fn get_mut<C: Component>(&self, entity: Entity) -> Result<hecs::RefMut<'_, C>> {
+        self.check_entity::<&mut C>(entity)?;
+
+        match self.world.get::<&mut C>(entity) {
+            Ok(val) => Ok(val),
+            Err(hecs::ComponentError::NoSuchEntity) => Err(Error::NoSuchEntity(entity)),
+            Err(hecs::ComponentError::MissingComponent(name)) => {
+                Err(Error::MissingComponent(entity, name))
+            }
+        }
+    }
+
+    /// Reserve an entity
+    pub fn reserve(&self) -> Entity {
+        self.world.reserve_entity()
+    }
+
+    /// Fails if `U` of `entity` can not be accessed
+    fn check_entity<U: IntoAccess>(&self, entity: Entity) -> Result<()> {
+        let access = U::access();
+        if !self.has_dynamic(access.id, access.exclusive) {
+            return Err(self.incompatible::<U>());
+        }
+
+        let entity_ref = self
+            .world
+            .entity(entity)
+            .map_err(|_| Error::NoSuchEntity(entity))?;
+
+        let has = |id| entity_ref.component_types().any(|val| val == id);
+        if !self.allows(access.id, access.exclusive, has) {
+            return Err(Error::UnsatisfiedQuery(entity, type_name::<Self>()));
+        }
+
+        Ok(())
+    }
+
+    /// Iterates the archetypes which contain entities
+    fn archetypes(&self) -> impl Iterator<Item = &Archetype> + '_ {
+        self.world.archetypes().filter(|arch| !arch.is_empty())
+    }
+}
+
+impl<'a> ContextBorrow<'a> for DynamicSubWorld<'a> {
+    type Target = Self;
+
+    fn borrow(context: &'a Context) -> Result<Self> {
+        let access = context.state::<DynamicAccess>()?.0.clone();
+
+        let val = context
+            .cell::<&World>()?
+            .try_borrow()
+            .map_err(|_| Error::Borrow(type_name::<World>()))
+            .map(|cell| AtomicRef::map(cell, |val| unsafe { val.cast().as_ref() }))?;
+
+        Ok(Self::new(val, access))
+    }
+}
+
+impl<A> ComponentBorrow for DynamicSubWorldRaw<A> {
+    // The component accesses are declared by
+    // `ScheduleBuilder::add_dynamic_system`
+    fn borrows() -> Borrows {
+        smallvec![Access::of::<&World>()]
+    }
+
+    fn has<U: IntoAccess>() -> bool {
+        let access = U::access();
+        <Self as ComponentBorrow>::has_dynamic(access.id, access.exclusive)
+    }
+
+    fn has_dynamic(id: TypeId, exclusive: bool) -> bool {
+        id == TypeId::of::<World>() && !exclusive
+    }
+}
+
+#[doc(hidden)]
+pub struct DynamicSubWorldBorrower;
+
+impl IntoBorrow for DynamicSubWorld<'_> {
+    type Borrow = DynamicSubWorldBorrower;
+}
+
+impl<'a> ContextBorrow<'a> for DynamicSubWorldBorrower {
+    type Target = DynamicSubWorld<'a>;
+
+    fn borrow(context: &'a Context) -> Result<Self::Target> {
+        Self::Target::borrow(context)
+    }
+}
+
+impl ScheduleBuilder {
+    /// Adds a system which accesses the components given by `access` through
+    /// a [`DynamicSubWorld`] parameter.
+    ///
+    /// The accesses are part of the borrows of the system, and thus decide
+    /// which systems it can run in parallel with.
+    pub fn add_dynamic_system<Args, Ret, S>(
+        &mut self,
+        access: impl IntoIterator<Item = Access>,
+        system: S,
+    ) -> &mut Self
+    where
+        S: 'static + System<Args, Ret> + Send,
+    {
+        let system = DynamicSystem::new(system).with_access(access.into_iter().collect());
+        self.add_internal(system);
+        self
+    }
+}
@@ -3,7 +3,7 @@
 use hecs::Entity;
 use thiserror::*;
 
-use crate::SystemName;
+use crate::{CommandKind, SystemName};
 
 #[doc(hidden)]
 pub type Result<T> = std::result::Result<T, Error>;
@@ -31,10 +31,30 @@ pub enum Error {
     #[doc(hidden)]
     UnsatisfiedQuery(Entity, &'static str),
 
+    #[error("Subworld splits {0:?} and {1:?} have conflicting accesses")]
+    #[doc(hidden)]
+    ConflictingSplit(&'static str, &'static str),
+
+    #[error("Entity {0:?} is outside the scope of the subworld")]
+    #[doc(hidden)]
+    OutOfScope(Entity),
+
+    #[error("Entity {0:?} has more than one marker of the partition {1:?}")]
+    #[doc(hidden)]
+    OverlappingPartition(Entity, &'static str),
+
+    #[error("Setting the parent of {0:?} to {1:?} would create a cycle")]
+    #[doc(hidden)]
+    HierarchyCycle(Entity, Entity),
+
     #[error("Context does not have data of type {0:?}")]
     #[doc(hidden)]
     MissingData(&'static str),
 
+    #[error("System state of type {0:?} is not available. Systems with state need to be executed by a schedule")]
+    #[doc(hidden)]
+    MissingState(&'static str),
+
     #[error("Data of type {0:?} is already mutable borrowed")]
     #[doc(hidden)]
     Borrow(&'static str),
@@ -46,4 +66,48 @@ pub enum Error {
     #[error("Failed to execute system {0:#?}")]
     #[doc(hidden)]
     SystemError(SystemName, #[source] anyhow::Error),
+
+    #[error(transparent)]
+    #[doc(hidden)]
+    Commands(#[from] CommandErrors),
+}
+
+#[derive(Debug, Error)]
+#[error("Failed to apply {kind:?} command on entity {entity:?}")]
+/// A recorded command which could not be applied to the world
+pub struct CommandError {
+    /// The entity the command targeted
+    pub entity: Entity,
+    /// The kind of command
+    pub kind: CommandKind,
+    /// The system which recorded the command, if set by
+    /// [`CommandBuffer::set_system`](crate::CommandBuffer::set_system)
+    pub system: Option<SystemName>,
+    /// The reason the command failed
+    #[source]
+    pub error: Error,
+}
+
+#[derive(Debug, Error)]
+#[error("{} commands failed to apply", .0.len())]
+/// All commands which failed during
+/// [`CommandBuffer::try_execute`](crate::CommandBuffer::try_execute)
+pub struct CommandErrors(pub Vec<CommandError>);
+
+impl std::ops::Deref for CommandErrors {
+    type Target = [CommandError];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl IntoIterator for CommandErrors {
+    type Item = CommandError;
+
+    type IntoIter = std::vec::IntoIter<CommandError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
 }
@@ -0,0 +1,223 @@
+use std::marker::PhantomData;
+
+use atomic_refcell::AtomicRefMut;
+use hecs::Component;
+
+use crate::{
+    borrow::{Borrows, ComponentBorrow, ContextBorrow},
+    system::SystemState,
+    Context, IntoAccess, Read, Result, ScheduleBuilder, System, Write,
+};
+
+/// A double buffered queue of events of type `T`, provided to a schedule as
+/// any other resource.
+///
+/// Events are sent through [`EventWriter`] and received through
+/// [`EventReader`]. Each event is kept for two calls to [`Self::update`],
+/// which allows every reader to observe it once regardless of whether it runs
+/// before or after the writer.
+pub struct Events<T> {
+    previous: Vec<T>,
+    current: Vec<T>,
+    /// Id of the first event in `previous`
+    start: usize,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+            start: 0,
+        }
+    }
+}
+
+impl<T> Events<T> {
+    /// Creates a new empty event queue
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends an event
+    pub fn send(&mut self, event: T) {
+        self.current.push(event)
+    }
+
+    /// Sends many events
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        self.current.extend(events)
+    }
+
+    /// Drops the events sent before the previous update, and starts a new
+    /// update cycle.
+    pub fn update(&mut self) {
+        self.start += self.previous.len();
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.clear();
+    }
+
+    /// Drops all events
+    pub fn clear(&mut self) {
+        self.update();
+        self.update();
+    }
+
+    /// Returns the number of live events
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    /// Returns true if there are no live events
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates all live events, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.previous.iter().chain(&self.current)
+    }
+
+    /// Returns the id of the next event to be sent
+    fn end(&self) -> usize {
+        self.start + self.len()
+    }
+
+    /// Iterates the live events starting from the event with id `cursor`
+    fn iter_from(&self, cursor: usize) -> impl Iterator<Item = &T> {
+        self.iter().skip(cursor.saturating_sub(self.start))
+    }
+}
+
+impl ScheduleBuilder {
+    /// Adds a system which updates [`Events<T>`] each time the schedule is
+    /// executed. This is usually placed at the start of the schedule.
+    pub fn add_event<T: Component>(&mut self) -> &mut Self {
+        self.add_system(
+            (|mut events: Write<Events<T>>| events.update())
+                .named(format!("update {}", std::any::type_name::<T>())),
+        )
+    }
+}
+
+/// The id of the next unread event of a reader
+struct ReaderCursor<T> {
+    next: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for ReaderCursor<T> {
+    fn default() -> Self {
+        Self {
+            next: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Sends events of type `T`. Requires exclusive access to [`Events<T>`].
+pub struct EventWriter<'a, T> {
+    events: Write<'a, Events<T>>,
+}
+
+impl<'a, T> EventWriter<'a, T> {
+    /// Sends an event
+    pub fn send(&mut self, event: T) {
+        self.events.send(event)
+    }
+
+    /// Sends many events
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        self.events.send_batch(events)
+    }
+}
+
+/// Receives the events of type `T` which were sent since the system last read
+/// them.
+///
+/// Each system keeps track of its own position, and only requires shared
+/// access to [`Events<T>`], which allows readers to run in parallel. A system
+/// may only have one reader of `T`. Adding a system with more panics.
+pub struct EventReader<'a, T> {
+    events: Read<'a, Events<T>>,
+    cursor: AtomicRefMut<'a, ReaderCursor<T>>,
+}
+
+impl<'a, T> EventReader<'a, T> {
+    /// Returns the unread events, and marks them as read
+    pub fn read(&mut self) -> impl Iterator<Item = &T> {
+        let start = std::mem::replace(&mut self.cursor.next, self.events.end());
+        self.events.iter_from(start)
+    }
+
+    /// Returns the number of unread events
+    pub fn len(&self) -> usize {
+        self.events.iter_from(self.cursor.next).count()
+    }
+
+    /// Returns true if there are no unread events
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Marks all events as read
+    pub fn clear(&mut self) {
+        self.cursor.next = self.events.end();
+    }
+}
+
+impl<'a, T: 'static> ContextBorrow<'a> for EventWriter<'a, T> {
+    type Target = Self;
+
+    fn borrow(context: &'a Context) -> Result<Self::Target> {
+        Ok(Self {
+            events: Write::borrow(context)?,
+        })
+    }
+}
+
+impl<'a, T: 'static> ContextBorrow<'a> for EventReader<'a, T> {
+    type Target = Self;
+
+    fn borrow(context: &'a Context) -> Result<Self::Target> {
+        Ok(Self {
+            events: Read::borrow(context)?,
+            cursor: context.state()?,
+        })
+    }
+}
+
+impl<'a, T: 'static> ComponentBorrow for EventWriter<'a, T> {
+    fn borrows() -> Borrows {
+        Write::<Events<T>>::borrows()
+    }
+
+    fn has<U: IntoAccess>() -> bool {
+        Write::<Events<T>>::has::<U>()
+    }
+
+    fn has_dynamic(id: std::any::TypeId, exclusive: bool) -> bool {
+        Write::<Events<T>>::has_dynamic(id, exclusive)
+    }
+}
+
+impl<'a, T: 'static> ComponentBorrow for EventReader<'a, T> {
+    fn borrows() -> Borrows {
+        Read::<Events<T>>::borrows()
+    }
+
+    fn has<U: IntoAccess>() -> bool {
+        Read::<Events<T>>::has::<U>()
+    }
+
+    fn has_dynamic(id: std::any::TypeId, exclusive: bool) -> bool {
+        Read::<Events<T>>::has_dynamic(id, exclusive)
+    }
+
+    fn init_state(state: &mut SystemState) {
+        state.init_unique::<ReaderCursor<T>>()
+    }
+}
+
+impl_into_borrow!(Component, EventWriter => EventWriterBorrower);
+impl_into_borrow!(Component, EventReader => EventReaderBorrower);
@@ -0,0 +1,99 @@
+//! This module provides archetype filters, which restrict a subworld to the
+//! entities having or lacking certain components.
+//!
+//! The accesses of a filtered subworld carry the filter. Systems writing the
+//! same component can thus run in the same batch as long as one requires a
+//! component the other excludes.
+//!
+//! ```
+//! use hecs::World;
+//! use hecs_schedule::{filter::{With, Without}, Schedule, SubWorld};
+//!
+//! struct Player;
+//! struct Transform(f32);
+//!
+//! let mut world = World::default();
+//! world.spawn((Player, Transform(0.0)));
+//! world.spawn((Transform(0.0),));
+//!
+//! let schedule = Schedule::builder()
+//!     .add_system(|w: SubWorld<&mut Transform, With<Player>>| {
+//!         w.query::<&mut Transform>().iter().for_each(|(_, t)| t.0 += 1.0);
+//!     })
+//!     .add_system(|w: SubWorld<&mut Transform, Without<Player>>| {
+//!         w.query::<&mut Transform>().iter().for_each(|(_, t)| t.0 -= 1.0);
+//!     })
+//!     .build();
+//!
+//! // Both systems run in the same batch, followed by the final flush
+//! assert_eq!(schedule.batch_info().len(), 2);
+//! ```
+use std::{
+    any::{type_name, TypeId},
+    marker::PhantomData,
+};
+
+use hecs::{Component, Query};
+
+/// Restricts a subworld to the entities passing the filter.
+///
+/// Implemented for `()`, which does not filter, [`With`], [`Without`], and
+/// tuples of these.
+pub trait ArchetypeFilter: 'static {
+    /// The query `Q` restricted to the entities passing the filter
+    type Query<Q: Query>: Query;
+
+    /// Calls `f` with each filtered component and whether it is required
+    /// (`true`) or excluded (`false`)
+    fn for_each_component(f: &mut impl FnMut(TypeId, &'static str, bool));
+}
+
+/// Filter for the entities which have the component `C`
+pub struct With<C>(PhantomData<C>);
+
+/// Filter for the entities which do not have the component `C`
+pub struct Without<C>(PhantomData<C>);
+
+impl ArchetypeFilter for () {
+    type Query<Q: Query> = Q;
+
+    fn for_each_component(_: &mut impl FnMut(TypeId, &'static str, bool)) {}
+}
+
+impl<C: Component> ArchetypeFilter for With<C> {
+    type Query<Q: Query> = hecs::With<Q, &'static C>;
+
+    fn for_each_component(f: &mut impl FnMut(TypeId, &'static str, bool)) {
+        f(TypeId::of::<C>(), type_name::<C>(), true)
+    }
+}
+
+impl<C: Component> ArchetypeFilter for Without<C> {
+    type Query<Q: Query> = hecs::Without<Q, &'static C>;
+
+    fn for_each_component(f: &mut impl FnMut(TypeId, &'static str, bool)) {
+        f(TypeId::of::<C>(), type_name::<C>(), false)
+    }
+}
+
+/// Applies each filter of a tuple to the query in turn
+macro_rules! nest {
+    ($query: ty;) => { $query };
+    ($query: ty; $head: ident $(, $tail: ident)*) => {
+        nest!(<$head as ArchetypeFilter>::Query<$query>; $($tail),*)
+    };
+}
+
+macro_rules! tuple_impl {
+    ($($name: ident),*) => {
+        impl<$($name: ArchetypeFilter),*> ArchetypeFilter for ($($name,)*) {
+            type Query<Q: Query> = nest!(Q; $($name),*);
+
+            fn for_each_component(f: &mut impl FnMut(TypeId, &'static str, bool)) {
+                $($name::for_each_component(f);)*
+            }
+        }
+    };
+}
+
+impl_for_tuples!(tuple_impl);
@@ -0,0 +1,348 @@
+//! Provides parent and child relationships between entities.
+//!
+//! The [`Parent`] component is the source of truth, and [`Children`] is kept
+//! consistent with it by [`sync_children`].
+//!
+//! Entities are reparented using [`CommandBuffer::set_parent`] and
+//! [`CommandBuffer::remove_parent`], which update both components at once.
+//! A hierarchy can be despawned at once using
+//! [`CommandBuffer::despawn_recursive`].
+//!
+//! Values such as transforms or visibility are propagated from parents to
+//! children by implementing [`Propagate`] and adding the [`propagate`] system.
+use std::{
+    any::type_name,
+    collections::{HashMap, VecDeque},
+    ops::Deref,
+};
+
+use hecs::{Component, Entity, World};
+use smallvec::SmallVec;
+
+use crate::{
+    borrow::ComponentBorrow, CommandBuffer, Error, GenericWorld, Result, ScheduleBuilder, SubWorld,
+    SubWorldRaw, Write,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Marks the entity as a child of another entity
+pub struct Parent(pub Entity);
+
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+/// The direct children of an entity.
+///
+/// Maintained from the [`Parent`] components by [`sync_children`].
+pub struct Children(pub(crate) SmallVec<[Entity; 8]>);
+
+impl Children {
+    /// Returns the children as a slice
+    pub fn as_slice(&self) -> &[Entity] {
+        &self.0
+    }
+}
+
+impl Deref for Children {
+    type Target = [Entity];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromIterator<Entity> for Children {
+    fn from_iter<T: IntoIterator<Item = Entity>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+/// Keeps [`Children`] consistent with the [`Parent`] components.
+///
+/// Children which no longer refer to the parent are removed, and new children
+/// are appended in query order. Parents without a [`Children`] component
+/// receive one when the commandbuffer is flushed. [`Parent`] components
+/// referring to a despawned entity are removed.
+pub fn sync_children(world: SubWorld<(&Parent, &mut Children)>, mut cmd: Write<CommandBuffer>) {
+    for (parent, children) in world.query::<&mut Children>().iter() {
+        children.0.retain(|child| {
+            world
+                .get::<Parent>(*child)
+                .map(|p| p.0 == parent)
+                .unwrap_or_default()
+        });
+    }
+
+    let mut missing: HashMap<Entity, Children> = HashMap::new();
+
+    for (child, &Parent(parent)) in world.query::<&Parent>().iter() {
+        match world.get_mut::<Children>(parent) {
+            Ok(mut children) => {
+                if !children.contains(&child) {
+                    children.0.push(child)
+                }
+            }
+            Err(Error::MissingComponent(..)) => missing.entry(parent).or_default().0.push(child),
+            Err(_) => cmd.remove_one::<Parent>(child),
+        }
+    }
+
+    for (parent, children) in missing {
+        cmd.insert_one(parent, children);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The order in which [`Descendants`] visits the hierarchy
+pub enum TraversalOrder {
+    /// Visit the whole subtree of a child before its next sibling
+    DepthFirst,
+    /// Visit all entities at one depth before the next depth
+    BreadthFirst,
+}
+
+/// Iterates the descendants of an entity, as given by [`Children`], excluding
+/// the entity itself.
+///
+/// Entities whose [`Children`] can not be accessed are treated as leaves.
+pub struct Descendants<'a, W> {
+    world: &'a W,
+    queue: VecDeque<Entity>,
+    order: TraversalOrder,
+}
+
+impl<'a, W: GenericWorld> Descendants<'a, W> {
+    /// Creates an iterator over the descendants of `root`
+    pub fn new(world: &'a W, root: Entity, order: TraversalOrder) -> Self {
+        let mut descendants = Self {
+            world,
+            queue: VecDeque::new(),
+            order,
+        };
+
+        descendants.push_children(root);
+        descendants
+    }
+
+    fn push_children(&mut self, entity: Entity) {
+        if let Ok(children) = self.world.try_get::<Children>(entity) {
+            match self.order {
+                // Popped from the back, so the first child is visited first
+                TraversalOrder::DepthFirst => self.queue.extend(children.iter().rev()),
+                TraversalOrder::BreadthFirst => self.queue.extend(children.iter()),
+            }
+        }
+    }
+}
+
+impl<'a, W: GenericWorld> Iterator for Descendants<'a, W> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entity = match self.order {
+            TraversalOrder::DepthFirst => self.queue.pop_back(),
+            TraversalOrder::BreadthFirst => self.queue.pop_front(),
+        }?;
+
+        self.push_children(entity);
+        Some(entity)
+    }
+}
+
+impl<A: Deref<Target = World>, T: ComponentBorrow> SubWorldRaw<A, T> {
+    /// Iterates the descendants of `root` in the given order.
+    ///
+    /// Fails if the subworld can not access [`Children`].
+    pub fn descendants(
+        &self,
+        root: Entity,
+        order: TraversalOrder,
+    ) -> Result<Descendants<'_, Self>> {
+        if !self.has::<&Children>() {
+            return Err(Error::IncompatibleSubworld {
+                subworld: type_name::<T>(),
+                query: type_name::<&Children>(),
+            });
+        }
+
+        Ok(Descendants::new(self, root, order))
+    }
+}
+
+/// Describes how a value is propagated from parents to children, such as a
+/// local transform being combined with the parent's global transform.
+///
+/// The propagated value is stored in [`Self::Output`] on each entity.
+pub trait Propagate: Component {
+    /// The propagated value
+    type Output: Component + Clone;
+
+    /// Returns the value of an entity without a parent
+    fn root(&self) -> Self::Output;
+
+    /// Returns the value of a child given the value of its parent
+    fn propagate(&self, parent: &Self::Output) -> Self::Output;
+}
+
+/// Propagates `T` from each root through the hierarchy, writing the result
+/// to `T::Output`.
+///
+/// Children without `T` are skipped along with their descendants. Entities
+/// without `T::Output` still propagate to their children.
+pub fn propagate<T: Propagate>(world: SubWorld<(&T, &mut T::Output, &Parent, &Children)>) {
+    let roots: Vec<_> = world
+        .query::<(&T, Option<&Parent>)>()
+        .iter()
+        .filter(|(_, (_, parent))| parent.is_none())
+        .map(|(entity, (value, _))| (entity, value.root()))
+        .collect();
+
+    let mut stack = Vec::new();
+    for (root, output) in roots {
+        stack.push((root, output));
+
+        while let Some((entity, output)) = stack.pop() {
+            if let Ok(children) = world.get::<Children>(entity) {
+                for &child in children.iter() {
+                    if let Ok(value) = world.get::<T>(child) {
+                        stack.push((child, value.propagate(&output)));
+                    }
+                }
+            }
+
+            if let Ok(mut current) = world.get_mut::<T::Output>(entity) {
+                *current = output;
+            }
+        }
+    }
+}
+
+impl ScheduleBuilder {
+    /// Adds the systems which keep the [`Parent`] and [`Children`]
+    /// components consistent, followed by a flush.
+    pub fn add_hierarchy_systems(&mut self) -> &mut Self {
+        self.add_system(sync_children).flush()
+    }
+}
+
+/// Returns `root` followed by all its descendants in depth first order
+pub(crate) fn subtree(world: &World, root: Entity) -> Vec<Entity> {
+    let mut result = Vec::new();
+    let mut stack = vec![root];
+
+    while let Some(entity) = stack.pop() {
+        result.push(entity);
+        if let Ok(children) = world.get::<&Children>(entity) {
+            stack.extend(children.iter().rev())
+        }
+    }
+
+    result
+}
+
+/// Returns the entities affected by despawning `root` recursively, which
+/// includes the parent of `root` as it is detached.
+pub(crate) fn despawn_recursive_targets(world: &World, root: Entity) -> Vec<Entity> {
+    let mut targets = subtree(world, root);
+    if let Ok(parent) = world.get::<&Parent>(root) {
+        targets.push(parent.0);
+    }
+
+    targets
+}
+
+/// Despawns `root` and all its descendants, and detaches `root` from its
+/// parent.
+pub(crate) fn despawn_recursive(world: &mut World, root: Entity) -> Result<()> {
+    if !world.contains(root) {
+        return Err(Error::NoSuchEntity(root));
+    }
+
+    let parent = world.get::<&Parent>(root).map(|p| p.0);
+    if let Ok(parent) = parent {
+        if let Ok(mut children) = world.get::<&mut Children>(parent) {
+            children.0.retain(|&mut child| child != root);
+        }
+    }
+
+    for entity in subtree(world, root) {
+        let _ = world.despawn(entity);
+    }
+
+    Ok(())
+}
+
+/// Returns the entities affected by setting the parent of `child`, which
+/// includes the previous and new parent.
+pub(crate) fn set_parent_targets(
+    world: &World,
+    child: Entity,
+    parent: Option<Entity>,
+) -> Vec<Entity> {
+    let mut targets = vec![child];
+    if let Ok(old) = world.get::<&Parent>(child) {
+        targets.push(old.0);
+    }
+    targets.extend(parent);
+
+    targets
+}
+
+/// Sets or removes the parent of `child`, updating [`Parent`] and the
+/// [`Children`] of both the previous and new parent.
+pub(crate) fn set_parent(world: &mut World, child: Entity, parent: Option<Entity>) -> Result<()> {
+    if !world.contains(child) {
+        return Err(Error::NoSuchEntity(child));
+    }
+
+    if let Some(parent) = parent {
+        if !world.contains(parent) {
+            return Err(Error::NoSuchEntity(parent));
+        }
+
+        // Walk up from the new parent to ensure `child` is not an ancestor
+        let mut ancestor = Some(parent);
+        while let Some(current) = ancestor {
+            if current == child {
+                return Err(Error::HierarchyCycle(child, parent));
+            }
+            ancestor = world.get::<&Parent>(current).map(|p| p.0).ok();
+        }
+    }
+
+    let old = world.get::<&Parent>(child).map(|p| p.0).ok();
+    if old == parent {
+        return Ok(());
+    }
+
+    if let Some(old) = old {
+        if let Ok(mut children) = world.get::<&mut Children>(old) {
+            children.0.retain(|&mut c| c != child);
+        }
+    }
+
+    match parent {
+        Some(parent) => {
+            world
+                .insert_one(child, Parent(parent))
+                .map_err(|_| Error::NoSuchEntity(child))?;
+
+            let missing = match world.get::<&mut Children>(parent) {
+                Ok(mut children) => {
+                    children.0.push(child);
+                    false
+                }
+                Err(_) => true,
+            };
+
+            if missing {
+                world
+                    .insert_one(parent, Children(SmallVec::from_slice(&[child])))
+                    .map_err(|_| Error::NoSuchEntity(parent))?;
+            }
+        }
+        None => {
+            let _ = world.remove_one::<Parent>(child);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use std::{any::TypeId, collections::HashMap, sync::Arc};
+
+use hecs::{Component, Entity, World};
+
+use crate::{commandbuffer::ComponentIds, removal::component_types};
+
+type Hook = Arc<dyn Fn(&mut World, Entity) + Send + Sync>;
+
+#[derive(Default, Clone)]
+struct ComponentHooks {
+    on_add: Vec<Hook>,
+    on_insert: Vec<Hook>,
+    on_remove: Vec<Hook>,
+}
+
+#[derive(Default, Clone)]
+/// Registry of callbacks which run when a component is added, inserted or
+/// removed by a [`CommandBuffer`](crate::CommandBuffer) flush.
+///
+/// - `on_add` runs after the component is inserted into an entity which did
+///   not have it.
+/// - `on_insert` runs after the component is inserted, including when it
+///   replaces an existing value.
+/// - `on_remove` runs before the component is removed, including when the
+///   entity is despawned, and can thus still access the value. It does not
+///   run when the removal would fail, such as when removing a bundle which
+///   is not fully present.
+///
+/// Hooks are registered on the [`ScheduleBuilder`](crate::ScheduleBuilder)
+/// and are used by every flush of the schedule. Components modified by custom
+/// writes do not run hooks.
+pub struct Hooks {
+    hooks: HashMap<TypeId, ComponentHooks>,
+}
+
+impl Hooks {
+    /// Creates a new empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a callback which runs when `C` is added to an entity
+    pub fn on_add<C: Component>(
+        &mut self,
+        hook: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.entry::<C>().on_add.push(Arc::new(hook));
+        self
+    }
+
+    /// Registers a callback which runs when `C` is added to or replaced in an
+    /// entity
+    pub fn on_insert<C: Component>(
+        &mut self,
+        hook: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.entry::<C>().on_insert.push(Arc::new(hook));
+        self
+    }
+
+    /// Registers a callback which runs before `C` is removed from an entity
+    pub fn on_remove<C: Component>(
+        &mut self,
+        hook: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.entry::<C>().on_remove.push(Arc::new(hook));
+        self
+    }
+
+    /// Returns true if no hooks are registered
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Moves all hooks from `other` into self
+    pub fn append(&mut self, other: &mut Hooks) {
+        for (id, mut hooks) in other.hooks.drain() {
+            let entry = self.hooks.entry(id).or_default();
+            entry.on_add.append(&mut hooks.on_add);
+            entry.on_insert.append(&mut hooks.on_insert);
+            entry.on_remove.append(&mut hooks.on_remove);
+        }
+    }
+
+    fn entry<C: Component>(&mut self) -> &mut ComponentHooks {
+        self.hooks.entry(TypeId::of::<C>()).or_default()
+    }
+
+    /// Runs the `on_remove` hooks of the components about to be removed
+    pub(crate) fn removing(&self, world: &mut World, removed: &[(Entity, TypeId)]) {
+        for &(entity, ty) in removed {
+            if let Some(hooks) = self.hooks.get(&ty) {
+                hooks.on_remove.iter().for_each(|hook| hook(world, entity))
+            }
+        }
+    }
+
+    /// Runs the `on_add` and `on_insert` hooks of an entity which had the
+    /// components `before`, and had the components `explicit` inserted.
+    pub(crate) fn inserted(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        before: &[TypeId],
+        explicit: &[TypeId],
+    ) {
+        let after = component_types(world, entity);
+        let added: ComponentIds = after
+            .iter()
+            .copied()
+            .filter(|ty| !before.contains(ty))
+            .collect();
+
+        for ty in &added {
+            if let Some(hooks) = self.hooks.get(ty) {
+                hooks.on_add.iter().for_each(|hook| hook(world, entity))
+            }
+        }
+
+        for ty in after {
+            if !added.contains(&ty) && !explicit.contains(&ty) {
+                continue;
+            }
+
+            if let Some(hooks) = self.hooks.get(&ty) {
+                hooks.on_insert.iter().for_each(|hook| hook(world, entity))
+            }
+        }
+    }
+}
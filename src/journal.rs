@@ -0,0 +1,176 @@
+use std::{any::TypeId, collections::HashSet};
+
+use hecs::{Component, Entity, EntityBuilder, EntityRef, World};
+
+use crate::{
+    commandbuffer::{Command, RemoveCommand},
+    CommandBuffer,
+};
+
+struct CloneEntry {
+    id: TypeId,
+    clone: fn(&EntityRef, &mut EntityBuilder),
+    has: fn(&EntityRef) -> bool,
+    remove: RemoveCommand,
+}
+
+#[derive(Default)]
+/// Registry of cloneable components used to capture the previous state of
+/// entities when executing a [`CommandBuffer`] with
+/// [`CommandBuffer::execute_with_inverse`].
+///
+/// Only registered components can be restored.
+pub struct CloneRegistry {
+    entries: Vec<CloneEntry>,
+}
+
+impl CloneRegistry {
+    /// Creates a new empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the component `C`. Registering a component more than once
+    /// has no effect.
+    pub fn register<C: Component + Clone>(&mut self) -> &mut Self {
+        if !self.contains::<C>() {
+            self.entries.push(CloneEntry {
+                id: TypeId::of::<C>(),
+                clone: |entity, builder| {
+                    if let Some(val) = entity.get::<&C>() {
+                        builder.add(C::clone(&val));
+                    }
+                },
+                has: |entity| entity.has::<C>(),
+                remove: RemoveCommand::one::<C>(),
+            });
+        }
+
+        self
+    }
+
+    /// Returns true if `C` is registered
+    pub fn contains<C: Component>(&self) -> bool {
+        self.entries.iter().any(|e| e.id == TypeId::of::<C>())
+    }
+
+    /// Clones the registered components of `entity`
+    fn snapshot(&self, entity: &EntityRef) -> EntityBuilder {
+        let mut builder = EntityBuilder::new();
+        self.entries
+            .iter()
+            .for_each(|entry| (entry.clone)(entity, &mut builder));
+
+        builder
+    }
+}
+
+/// Records the inverse of applied commands
+pub(crate) struct Journal<'a> {
+    registry: &'a CloneRegistry,
+    /// Reserved entities which were flushed into the world without
+    /// components, and thus are not yet spawned
+    reserved: HashSet<Entity>,
+    groups: Vec<Vec<Command>>,
+}
+
+impl<'a> Journal<'a> {
+    pub(crate) fn new(registry: &'a CloneRegistry) -> Self {
+        Self {
+            registry,
+            reserved: HashSet::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    /// Flushes the reserved entities into the world, and remembers them.
+    ///
+    /// Flushed entities are appended to the archetype without components.
+    fn flush(&mut self, world: &mut World) {
+        let start = world.archetypes().next().map_or(0, |arch| arch.len()) as usize;
+        world.flush();
+
+        if let Some(arch) = world.archetypes().next() {
+            self.reserved.extend(
+                arch.ids()[start..]
+                    .iter()
+                    // SAFETY: the ids of an archetype are of live entities
+                    .map(|&id| unsafe { world.find_entity_from_id(id) }),
+            );
+        }
+    }
+
+    /// Captures the state of `entities` before a command is applied. Entities
+    /// which do not exist, or are reserved but not yet spawned, are captured
+    /// as None.
+    pub(crate) fn snapshot(
+        &mut self,
+        world: &mut World,
+        entities: &[Entity],
+    ) -> Vec<(Entity, Option<EntityBuilder>)> {
+        self.flush(world);
+
+        entities
+            .iter()
+            .map(|&entity| {
+                let before = world
+                    .entity(entity)
+                    .ok()
+                    .filter(|e| !(e.is_empty() && self.reserved.contains(&entity)))
+                    .map(|e| self.registry.snapshot(&e));
+
+                (entity, before)
+            })
+            .collect()
+    }
+
+    /// Records the commands which restore the captured state after a command
+    /// was applied
+    pub(crate) fn record(
+        &mut self,
+        world: &World,
+        snapshots: Vec<(Entity, Option<EntityBuilder>)>,
+        spawned: Vec<Entity>,
+    ) {
+        let mut group: Vec<_> = spawned.into_iter().map(Command::Despawn).collect();
+
+        for (entity, before) in snapshots {
+            // A reserved entity is spawned once it has components
+            if matches!(world.entity(entity), Ok(e) if !e.is_empty()) {
+                self.reserved.remove(&entity);
+            }
+
+            match (before, world.entity(entity)) {
+                (None, Ok(_)) => group.push(Command::Despawn(entity)),
+                (None, Err(_)) => {}
+                (Some(before), Err(_)) => group.push(Command::SpawnAt(entity, before)),
+                (Some(before), Ok(after)) => {
+                    // Remove the components which were added
+                    for entry in &self.registry.entries {
+                        let existed = before.component_types().any(|id| id == entry.id);
+                        if !existed && (entry.has)(&after) {
+                            group.push(Command::Remove(entity, entry.remove));
+                        }
+                    }
+
+                    group.push(Command::Insert(entity, before));
+                }
+            }
+        }
+
+        self.groups.push(group);
+    }
+
+    /// Returns the commands which restore the captured state, in reverse
+    /// order
+    pub(crate) fn finish(self) -> CommandBuffer {
+        let mut cmd = CommandBuffer::new();
+        self.groups
+            .into_iter()
+            .rev()
+            .flatten()
+            .for_each(|command| cmd.push(command, 0));
+
+        cmd
+    }
+}
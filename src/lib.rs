@@ -119,11 +119,26 @@ mod macros;
 mod access;
 #[macro_use]
 pub mod borrow;
+mod cached;
+mod change;
 mod commandbuffer;
 pub mod context;
+mod dynamic;
 pub mod error;
+mod events;
+pub mod filter;
+pub mod hierarchy;
+mod hooks;
+mod journal;
+mod observer;
+mod partition;
 mod query;
+mod removal;
 mod schedule;
+#[cfg(feature = "serde")]
+pub mod serialize;
+mod state;
+mod subset;
 mod subworld;
 mod subworld_impls;
 pub mod system;
@@ -131,10 +146,23 @@ pub mod traits;
 
 pub use access::*;
 pub use borrow::{Read, Write};
+pub use cached::CachedQuery;
+pub use change::{
+    Added, Changed, Filter, FilteredQuery, ResourceTicks, SystemTicks, Tick, Tracked,
+};
 pub use commandbuffer::*;
 pub use context::*;
+pub use dynamic::{DynamicSubWorld, DynamicSubWorldRaw, DynamicSubWorldRef};
 pub use error::Error;
+pub use events::{EventReader, EventWriter, Events};
+pub use hooks::Hooks;
+pub use journal::CloneRegistry;
+pub use observer::{OnAdd, OnInsert, OnRemove, Trigger, TriggerQueue, Triggered};
+pub use partition::{EntityScope, ScopedQuery, ScopedSubWorld};
 pub use query::*;
+pub use removal::{Removals, RemovedComponents};
+pub use state::{in_state, InState, NextState, State, StateValue};
+pub use subset::{HasRead, HasWrite, SubsetOf};
 pub use subworld_impls::*;
 // Don't export result so that hecs-schedule can be glob imported without
 // conflict
@@ -0,0 +1,213 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use hecs::{Component, Entity};
+use smallvec::smallvec;
+
+use crate::{
+    borrow::{Borrows, ComponentBorrow, ContextBorrow},
+    schedule::DynamicSystem,
+    Context, Error, Hooks, IntoAccess, Result, ScheduleBuilder, System,
+};
+
+/// An event which runs the observers registered through
+/// [`ScheduleBuilder::observe`].
+///
+/// Custom events implement the trait without any methods, and are sent through
+/// [`CommandBuffer::trigger`](crate::CommandBuffer::trigger). The component
+/// lifecycle is observed through [`OnAdd`], [`OnInsert`] and [`OnRemove`].
+pub trait Trigger: Component {
+    #[doc(hidden)]
+    /// Registers the hooks which send the trigger
+    fn register(_hooks: &mut Hooks, _queue: &TriggerQueue) {}
+}
+
+/// Triggered when `C` is added to an entity. See [`Hooks::on_add`].
+pub struct OnAdd<C>(PhantomData<fn() -> C>);
+
+/// Triggered when `C` is added to or replaced in an entity. See
+/// [`Hooks::on_insert`].
+pub struct OnInsert<C>(PhantomData<fn() -> C>);
+
+/// Triggered when `C` is removed from an entity. See [`Hooks::on_remove`].
+pub struct OnRemove<C>(PhantomData<fn() -> C>);
+
+macro_rules! lifecycle_trigger {
+    ($name: ident, $hook: ident) => {
+        impl<C: Component> Trigger for $name<C> {
+            fn register(hooks: &mut Hooks, queue: &TriggerQueue) {
+                let queue = queue.clone();
+                hooks
+                    .$hook::<C>(move |_, entity| queue.push(Some(entity), $name::<C>(PhantomData)));
+            }
+        }
+    };
+}
+
+lifecycle_trigger!(OnAdd, on_add);
+lifecycle_trigger!(OnInsert, on_insert);
+lifecycle_trigger!(OnRemove, on_remove);
+
+/// A trigger waiting for its observers to run
+pub(crate) struct PendingTrigger {
+    id: TypeId,
+    pub(crate) entity: Option<Entity>,
+    event: Box<dyn Any + Send + Sync>,
+}
+
+impl PendingTrigger {
+    pub(crate) fn new<E: Trigger>(entity: Option<Entity>, event: E) -> Self {
+        Self {
+            id: TypeId::of::<E>(),
+            entity,
+            event: Box::new(event),
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Default, Clone)]
+/// Triggers sent during a flush, which are shared with the hooks sending them
+pub struct TriggerQueue(Arc<Mutex<Vec<PendingTrigger>>>);
+
+impl TriggerQueue {
+    pub(crate) fn push(&self, entity: Option<Entity>, event: impl Trigger) {
+        self.push_pending(PendingTrigger::new(entity, event))
+    }
+
+    pub(crate) fn push_pending(&self, trigger: PendingTrigger) {
+        self.0.lock().unwrap().push(trigger)
+    }
+
+    fn take(&self) -> Vec<PendingTrigger> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+struct Observer {
+    trigger: TypeId,
+    register: fn(&mut Hooks, &TriggerQueue),
+    system: DynamicSystem,
+}
+
+#[derive(Default)]
+/// The observer systems of a schedule
+pub(crate) struct Observers {
+    observers: Vec<Observer>,
+    queue: TriggerQueue,
+}
+
+impl Observers {
+    pub(crate) fn queue(&self) -> &TriggerQueue {
+        &self.queue
+    }
+
+    /// Iterates the observer systems
+    pub(crate) fn systems(&self) -> impl Iterator<Item = &DynamicSystem> {
+        self.observers.iter().map(|observer| &observer.system)
+    }
+
+    pub(crate) fn append(&mut self, other: &mut Observers) {
+        self.observers.append(&mut other.observers)
+    }
+
+    /// Registers the hooks which send the observed lifecycle triggers
+    pub(crate) fn register(&self, hooks: &mut Hooks) {
+        let mut registered = Vec::new();
+        for observer in &self.observers {
+            if !registered.contains(&observer.trigger) {
+                registered.push(observer.trigger);
+                (observer.register)(hooks, &self.queue)
+            }
+        }
+    }
+
+    /// Runs the observers of the triggers sent since the last run, in the
+    /// order the triggers were sent
+    pub(crate) fn run(&mut self, context: &Context) -> Result<()> {
+        for trigger in self.queue.take() {
+            let context = context.with_trigger(&trigger);
+            self.observers
+                .iter_mut()
+                .filter(|observer| observer.trigger == trigger.id)
+                .try_for_each(|observer| observer.system.execute(&context))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ScheduleBuilder {
+    /// Adds a system which runs each time `E` is triggered, instead of every
+    /// execution of the schedule.
+    ///
+    /// Observers run sequentially after the commandbuffer flush which sent the
+    /// trigger, and may borrow any data provided to the schedule. The trigger
+    /// is accessed through the [`Triggered`] parameter.
+    pub fn observe<E: Trigger, Args, Ret, S>(&mut self, system: S) -> &mut Self
+    where
+        S: 'static + System<Args, Ret> + Send,
+    {
+        self.observers_mut().observers.push(Observer {
+            trigger: TypeId::of::<E>(),
+            register: E::register,
+            system: DynamicSystem::new(system),
+        });
+        self
+    }
+}
+
+/// Provides the trigger which caused an observer to run
+pub struct Triggered<'a, E> {
+    trigger: &'a PendingTrigger,
+    event: &'a E,
+}
+
+impl<'a, E> Triggered<'a, E> {
+    /// Returns the entity the trigger targets. Lifecycle triggers always
+    /// target an entity.
+    pub fn entity(&self) -> Option<Entity> {
+        self.trigger.entity
+    }
+
+    /// Returns the triggered event
+    pub fn event(&self) -> &'a E {
+        self.event
+    }
+}
+
+impl<'a, E: 'static> ContextBorrow<'a> for Triggered<'a, E> {
+    type Target = Self;
+
+    fn borrow(context: &'a Context) -> Result<Self::Target> {
+        let trigger = context
+            .trigger()
+            .ok_or(Error::MissingData(type_name::<Triggered<E>>()))?;
+
+        let event = trigger
+            .event
+            .downcast_ref()
+            .ok_or(Error::MissingData(type_name::<E>()))?;
+
+        Ok(Self { trigger, event })
+    }
+}
+
+impl<'a, E: 'static> ComponentBorrow for Triggered<'a, E> {
+    fn borrows() -> Borrows {
+        smallvec![]
+    }
+
+    fn has<U: IntoAccess>() -> bool {
+        false
+    }
+
+    fn has_dynamic(_: TypeId, _: bool) -> bool {
+        false
+    }
+}
+
+impl_into_borrow!(Component, Triggered => TriggeredBorrower);
@@ -0,0 +1,250 @@
+use std::{
+    any::{type_name, TypeId},
+    collections::HashSet,
+    ops::Deref,
+};
+
+use hecs::{Bundle, Component, Entity, Query, QueryBorrow, World};
+use smallvec::SmallVec;
+
+use crate::{
+    borrow::{ComponentBorrow, ContextBorrow, MaybeRead},
+    filter::ArchetypeFilter,
+    Access, Context, Error, QueryOne, Result, ScheduleBuilder, SubWorldRaw, Subset,
+};
+
+/// Marker components which never occur together on an entity
+struct Partition {
+    name: &'static str,
+    markers: SmallVec<[TypeId; 4]>,
+}
+
+impl Partition {
+    fn contains(&self, id: TypeId) -> bool {
+        self.markers.contains(&id)
+    }
+}
+
+#[derive(Default)]
+/// The partitions declared by [`ScheduleBuilder::partition`]
+pub(crate) struct Partitions(Vec<Partition>);
+
+impl Partitions {
+    /// Returns true if the accesses are restricted to different markers of
+    /// the same partition, and thus to different entities
+    pub(crate) fn separates(&self, a: &Access, b: &Access) -> bool {
+        self.0.iter().any(|partition| {
+            a.with().filter(|&id| partition.contains(id)).any(|marker| {
+                b.with()
+                    .any(|other| other != marker && partition.contains(other))
+            })
+        })
+    }
+
+    pub(crate) fn append(&mut self, other: &mut Self) {
+        self.0.append(&mut other.0)
+    }
+
+    /// Fails if an entity of the world has more than one marker of a
+    /// partition
+    pub(crate) fn check(&self, context: &Context) -> Result<()> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let world = MaybeRead::<World>::borrow(context)?;
+        let world = match world.option() {
+            Some(world) => world,
+            None => return Ok(()),
+        };
+
+        for archetype in world.archetypes().filter(|arch| !arch.is_empty()) {
+            for partition in &self.0 {
+                let count = partition
+                    .markers
+                    .iter()
+                    .filter(|&&id| archetype.has_dynamic(id))
+                    .count();
+
+                if count > 1 {
+                    // SAFETY: the ids of an archetype are of live entities
+                    let entity = unsafe { world.find_entity_from_id(archetype.ids()[0]) };
+                    return Err(Error::OverlappingPartition(entity, partition.name));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ScheduleBuilder {
+    /// Declares the marker components `P`, such as the teams of a game, to
+    /// partition the entities. An entity may have at most one of the markers.
+    ///
+    /// Systems whose subworlds are filtered by different markers, such as
+    /// `SubWorld<&mut Health, With<Red>>` and `SubWorld<&mut Health,
+    /// With<Blue>>`, can then share a batch. The partition applies to every
+    /// system of the schedule, including those added before it.
+    ///
+    /// Executing a batch which relies on the partition fails with
+    /// [`Error::OverlappingPartition`] if an entity has more than one of the
+    /// markers.
+    pub fn partition<P: Bundle>(&mut self) -> &mut Self {
+        let markers = P::with_static_ids(|ids| ids.into());
+        self.partitions_mut().0.push(Partition {
+            name: type_name::<P>(),
+            markers,
+        });
+        self
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+/// A set of entities, which a [`ScopedSubWorld`] is restricted to, such as
+/// the entities of a room.
+pub struct EntityScope {
+    entities: HashSet<Entity>,
+}
+
+impl EntityScope {
+    /// Creates an empty scope
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `entity` to the scope. Returns false if already present
+    pub fn insert(&mut self, entity: Entity) -> bool {
+        self.entities.insert(entity)
+    }
+
+    /// Removes `entity` from the scope. Returns false if not present
+    pub fn remove(&mut self, entity: Entity) -> bool {
+        self.entities.remove(&entity)
+    }
+
+    /// Returns true if `entity` is in the scope
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(&entity)
+    }
+
+    /// Returns the number of entities in the scope
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Returns true if the scope contains no entities
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Iterates the entities of the scope in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities.iter().copied()
+    }
+}
+
+impl FromIterator<Entity> for EntityScope {
+    fn from_iter<I: IntoIterator<Item = Entity>>(iter: I) -> Self {
+        Self {
+            entities: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<Entity> for EntityScope {
+    fn extend<I: IntoIterator<Item = Entity>>(&mut self, iter: I) {
+        self.entities.extend(iter)
+    }
+}
+
+/// A subworld restricted to the entities of an [`EntityScope`], created by
+/// [`SubWorldRaw::scoped`].
+///
+/// Accessing an entity outside the scope fails with [`Error::OutOfScope`].
+///
+/// The scope does not take part in batching, since entities of the same
+/// archetype share their component storage. Systems mutating the same
+/// component on disjoint entities share a batch when the entities are told
+/// apart by marker components, see [`ScheduleBuilder::partition`].
+pub struct ScopedSubWorld<'a, W> {
+    world: &'a W,
+    scope: &'a EntityScope,
+}
+
+impl<'a, W> ScopedSubWorld<'a, W> {
+    /// Returns the scope of the subworld
+    pub fn scope(&self) -> &'a EntityScope {
+        self.scope
+    }
+
+    fn check(&self, entity: Entity) -> Result<()> {
+        if self.scope.contains(entity) {
+            Ok(())
+        } else {
+            Err(Error::OutOfScope(entity))
+        }
+    }
+}
+
+impl<'a, A, T, F> ScopedSubWorld<'a, SubWorldRaw<A, T, F>>
+where
+    A: 'a + Deref<Target = World>,
+    T: ComponentBorrow,
+    F: ArchetypeFilter,
+{
+    /// Query the entities of the scope.
+    /// # Panics
+    /// Panics if the query items are not a compatible subset of the subworld.
+    pub fn query<Q: Query + Subset>(&self) -> ScopedQuery<'a, F::Query<Q>> {
+        ScopedQuery {
+            borrow: self.world.query::<Q>(),
+            scope: self.scope,
+        }
+    }
+
+    /// Query the subworld for a single entity of the scope
+    pub fn query_one<Q: Query + Subset>(
+        &self,
+        entity: Entity,
+    ) -> Result<QueryOne<'a, F::Query<Q>>> {
+        self.check(entity)?;
+        self.world.query_one(entity)
+    }
+
+    /// Get a single component of an entity of the scope
+    pub fn get<C: Component>(&self, entity: Entity) -> Result<hecs::Ref<'a, C>> {
+        self.check(entity)?;
+        self.world.get(entity)
+    }
+
+    /// Get a single component mutably of an entity of the scope. Requires
+    /// exclusive access to the component.
+    pub fn get_mut<C: Component>(&self, entity: Entity) -> Result<hecs::RefMut<'a, C>> {
+        self.check(entity)?;
+        self.world.get_mut(entity)
+    }
+}
+
+impl<A, T, F> SubWorldRaw<A, T, F> {
+    /// Restricts the subworld to the entities of `scope`
+    pub fn scoped<'a>(&'a self, scope: &'a EntityScope) -> ScopedSubWorld<'a, Self> {
+        ScopedSubWorld { world: self, scope }
+    }
+}
+
+/// A query borrow which only yields the entities of an [`EntityScope`]
+pub struct ScopedQuery<'w, Q: Query> {
+    borrow: QueryBorrow<'w, Q>,
+    scope: &'w EntityScope,
+}
+
+impl<'w, Q: Query> ScopedQuery<'w, Q> {
+    /// Iterates the entities of the scope which match the query
+    pub fn iter(&mut self) -> impl Iterator<Item = (Entity, Q::Item<'_>)> {
+        let scope = self.scope;
+        self.borrow
+            .iter()
+            .filter(move |(entity, _)| scope.contains(*entity))
+    }
+}
@@ -3,6 +3,11 @@ use std::any::type_name;
 use crate::{Error, Result};
 use hecs::{Entity, Query};
 
+#[cfg(feature = "parallel")]
+use hecs::{Batch, QueryBorrow};
+#[cfg(feature = "parallel")]
+use rayon::iter::{FlatMapIter, IntoParallelIterator, ParallelIterator};
+
 /// Wraps the bulting QueryOne with a Result containing the entity and component instead of option
 pub struct QueryOne<'a, Q: Query> {
     entity: Entity,
@@ -28,3 +33,201 @@ impl<'a, Q: Query> QueryOne<'a, Q> {
         }
     }
 }
+
+#[cfg(feature = "parallel")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Decides the number of entities per task of a parallel query
+pub enum BatchSize {
+    /// Tasks of at most the given number of entities
+    Fixed(u32),
+    /// Splits the matched entities between the rayon threads, with a few
+    /// tasks per thread to balance the load. Since a task never spans
+    /// archetypes, the size is chosen from the length of each matched
+    /// archetype, such that no more tasks than needed are created. The batch
+    /// size is clamped to `min..=max`.
+    Adaptive {
+        /// The smallest batch size
+        min: u32,
+        /// The largest batch size
+        max: u32,
+    },
+}
+
+#[cfg(feature = "parallel")]
+impl BatchSize {
+    /// The number of tasks per thread used by [`BatchSize::Adaptive`]
+    pub const TASKS_PER_THREAD: u32 = 4;
+
+    /// Adaptive batch sizes without bounds
+    pub fn adaptive() -> Self {
+        Self::Adaptive {
+            min: 1,
+            max: u32::MAX,
+        }
+    }
+
+    /// Decides the batch size for the entities matched by `query`
+    pub fn plan<Q: Query>(self, query: &mut QueryBorrow<'_, Q>) -> BatchPlan {
+        let mut plan = self.decide(query);
+        plan.batches = query.iter_batched(plan.size).count() as u32;
+        plan
+    }
+
+    /// Decides the batch size, without counting the resulting batches
+    fn decide<Q: Query>(self, query: &mut QueryBorrow<'_, Q>) -> BatchPlan {
+        let threads = rayon::current_num_threads();
+        let entities = query.iter().len() as u32;
+        // A batch size of `entities` yields one batch per matched archetype
+        let archetypes = query.iter_batched(entities.max(1)).count() as u32;
+
+        let size = match self {
+            Self::Fixed(size) => size,
+            Self::Adaptive { min, max } => {
+                let tasks = (threads as u32).saturating_mul(Self::TASKS_PER_THREAD);
+                let tasks = tasks.max(archetypes).max(1);
+
+                // The number of batches only decreases with the size, so
+                // search for the smallest size which does not exceed `tasks`
+                // when splitting each archetype
+                let mut hi = entities.max(1);
+                let mut lo = (hi - 1) / tasks + 1;
+                while lo < hi {
+                    let mid = lo + (hi - lo) / 2;
+                    if query.iter_batched(mid).count() as u32 <= tasks {
+                        hi = mid;
+                    } else {
+                        lo = mid + 1;
+                    }
+                }
+
+                lo.clamp(min, max.max(min))
+            }
+        }
+        .max(1);
+
+        BatchPlan {
+            batch_size: self,
+            entities,
+            archetypes,
+            threads,
+            size,
+            batches: 0,
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl Default for BatchSize {
+    fn default() -> Self {
+        Self::adaptive()
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl From<u32> for BatchSize {
+    fn from(size: u32) -> Self {
+        Self::Fixed(size)
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The batch size decided for a parallel query, see [`BatchSize::plan`]
+pub struct BatchPlan {
+    /// The requested batch size
+    pub batch_size: BatchSize,
+    /// The number of entities matched by the query
+    pub entities: u32,
+    /// The number of non-empty archetypes matched by the query
+    pub archetypes: u32,
+    /// The number of rayon threads
+    pub threads: usize,
+    /// The decided number of entities per task
+    pub size: u32,
+    /// The number of tasks the entities are split into
+    pub batches: u32,
+}
+
+#[cfg(feature = "parallel")]
+impl std::fmt::Display for BatchPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} entities of {} archetypes in {} batches of {} on {} threads ({:?})",
+            self.entities, self.archetypes, self.batches, self.size, self.threads, self.batch_size
+        )
+    }
+}
+
+#[cfg(feature = "parallel")]
+/// Splits the query into batches sized by `batch_size`, ordered by archetype
+/// and position in the archetype. Returns the plan the batches follow. Used by
+/// both [`ParQuery`] and [`QueryExt`](crate::QueryExt).
+pub(crate) fn batches<'q, Q: Query>(
+    query: &'q mut QueryBorrow<'_, Q>,
+    batch_size: BatchSize,
+) -> (BatchPlan, Vec<Batch<'q, Q>>) {
+    let mut plan = batch_size.decide(query);
+    let batches: Vec<_> = query.iter_batched(plan.size).collect();
+    plan.batches = batches.len() as u32;
+    (plan, batches)
+}
+
+#[cfg(feature = "parallel")]
+/// Parallel iterator over the entities of a [`ParQuery`]
+pub type ParQueryIter<'q, Q> =
+    FlatMapIter<rayon::vec::IntoIter<Batch<'q, Q>>, fn(Batch<'q, Q>) -> Batch<'q, Q>>;
+
+#[cfg(feature = "parallel")]
+/// A query borrow which is iterated in parallel using rayon, created by
+/// [`SubWorldRaw::query_par`](crate::SubWorldRaw::query_par).
+///
+/// The matched entities are split into tasks per archetype, sized by
+/// [`BatchSize`]. Adaptive sizing is used unless specified.
+pub struct ParQuery<'w, Q: Query> {
+    borrow: QueryBorrow<'w, Q>,
+    batch_size: BatchSize,
+    plan: Option<BatchPlan>,
+}
+
+#[cfg(feature = "parallel")]
+impl<'w, Q: Query> ParQuery<'w, Q> {
+    pub(crate) fn new(borrow: QueryBorrow<'w, Q>) -> Self {
+        Self {
+            borrow,
+            batch_size: BatchSize::default(),
+            plan: None,
+        }
+    }
+
+    /// Sets the number of entities per task
+    pub fn with_batch_size(mut self, batch_size: impl Into<BatchSize>) -> Self {
+        self.batch_size = batch_size.into();
+        self.plan = None;
+        self
+    }
+
+    /// Returns the plan followed by the last [`Self::par_iter`], or the plan
+    /// it will follow if not yet iterated
+    pub fn plan(&mut self) -> BatchPlan {
+        match self.plan {
+            Some(plan) => plan,
+            None => self.batch_size.plan(&mut self.borrow),
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<'w, Q: Query> ParQuery<'w, Q>
+where
+    for<'a> Q::Item<'a>: Send,
+{
+    /// Returns a parallel iterator over the matched entities, supporting the
+    /// adaptors of [`ParallelIterator`](rayon::iter::ParallelIterator), such
+    /// as `for_each`, `map`, `fold`, `reduce` and `collect`.
+    pub fn par_iter(&mut self) -> ParQueryIter<'_, Q> {
+        let (plan, batches) = batches(&mut self.borrow, self.batch_size);
+        self.plan = Some(plan);
+        batches.into_par_iter().flat_map_iter(|batch| batch)
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    any::{type_name, TypeId},
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{RwLock, RwLockReadGuard},
+};
+
+use hecs::{Component, Entity, World};
+use smallvec::smallvec;
+
+use crate::{
+    borrow::{Borrows, ComponentBorrow, ContextBorrow},
+    commandbuffer::ComponentIds,
+    Access, Context, Error, IntoAccess, Result, Tick,
+};
+
+type RemovalMap = HashMap<TypeId, Vec<(Entity, Tick)>>;
+
+#[derive(Default)]
+struct RemovalBuffers {
+    previous: RemovalMap,
+    current: RemovalMap,
+}
+
+#[derive(Default)]
+/// Records which components were removed from which entities, including
+/// components of despawned entities.
+///
+/// The [`Schedule`](crate::Schedule) records the removals performed by its
+/// commandbuffer flushes, as long as any of its systems reads them. Removals performed elsewhere, such as by exclusive
+/// edits of the world between executions, can be recorded through
+/// [`Schedule::removals`](crate::Schedule::removals).
+///
+/// Removals are kept for two executions of the schedule, and are read by
+/// systems through [`RemovedComponents`].
+pub struct Removals {
+    buffers: RwLock<RemovalBuffers>,
+}
+
+impl Removals {
+    /// Creates a new empty log
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that `entity` lost the component with type id `ty`
+    pub fn record_dynamic(&self, entity: Entity, ty: TypeId) {
+        self.buffers
+            .write()
+            .unwrap()
+            .current
+            .entry(ty)
+            .or_default()
+            .push((entity, Tick::now()))
+    }
+
+    /// Records that `entity` lost the component `C`
+    pub fn record<C: Component>(&self, entity: Entity) {
+        self.record_dynamic(entity, TypeId::of::<C>())
+    }
+
+    /// Removes a component from an entity and records the removal
+    pub fn remove_one<C: Component>(&self, world: &mut World, entity: Entity) -> Result<C> {
+        let val = world.remove_one::<C>(entity).map_err(|e| match e {
+            hecs::ComponentError::NoSuchEntity => Error::NoSuchEntity(entity),
+            hecs::ComponentError::MissingComponent(name) => Error::MissingComponent(entity, name),
+        })?;
+
+        self.record::<C>(entity);
+        Ok(val)
+    }
+
+    /// Despawns an entity and records the removal of all its components
+    pub fn despawn(&self, world: &mut World, entity: Entity) -> Result<()> {
+        let types = component_types(world, entity);
+        world
+            .despawn(entity)
+            .map_err(|_| Error::NoSuchEntity(entity))?;
+
+        types
+            .into_iter()
+            .for_each(|ty| self.record_dynamic(entity, ty));
+        Ok(())
+    }
+
+    /// Records the components which `entities` lost since the `snapshot` was
+    /// taken
+    pub(crate) fn record_snapshot(&self, world: &World, snapshot: RemovalSnapshot) {
+        for (entity, before) in snapshot {
+            let after = component_types(world, entity);
+            before
+                .into_iter()
+                .filter(|ty| !after.contains(ty))
+                .for_each(|ty| self.record_dynamic(entity, ty))
+        }
+    }
+
+    /// Drops the removals recorded before the previous update, and starts a
+    /// new update cycle
+    pub(crate) fn update(&mut self) {
+        let buffers = self.buffers.get_mut().unwrap();
+        buffers.previous = std::mem::take(&mut buffers.current);
+    }
+}
+
+pub(crate) type RemovalSnapshot = Vec<(Entity, ComponentIds)>;
+
+/// Returns the components of `entity`, or none if the entity does not exist
+pub(crate) fn component_types(world: &World, entity: Entity) -> ComponentIds {
+    world
+        .entity(entity)
+        .map(|entity| entity.component_types().collect())
+        .unwrap_or_default()
+}
+
+/// Captures the components of `entities` in order to record which are removed
+pub(crate) fn snapshot(world: &World, entities: &[Entity]) -> RemovalSnapshot {
+    entities
+        .iter()
+        .map(|&entity| (entity, component_types(world, entity)))
+        .collect()
+}
+
+/// Lists the entities which lost the component `T`, including by being
+/// despawned, since the system last ran.
+///
+/// Only requires shared access to the [`Removals`], and is thus ordered after
+/// commandbuffer flushes.
+pub struct RemovedComponents<'a, T> {
+    buffers: Option<RwLockReadGuard<'a, RemovalBuffers>>,
+    last_run: Tick,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, T: 'static> RemovedComponents<'a, T> {
+    /// Iterates the entities which lost `T` since the system last ran. An
+    /// entity may occur more than once.
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        let ty = TypeId::of::<T>();
+        self.buffers
+            .iter()
+            .flat_map(move |buffers| {
+                buffers
+                    .previous
+                    .get(&ty)
+                    .into_iter()
+                    .chain(buffers.current.get(&ty))
+                    .flatten()
+            })
+            .filter(move |(_, tick)| *tick > self.last_run)
+            .map(|&(entity, _)| entity)
+    }
+
+    /// Returns true if `entity` lost `T` since the system last ran
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.iter().any(|e| e == entity)
+    }
+
+    /// Returns true if no entity lost `T` since the system last ran
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+impl<'a, T: 'static> ContextBorrow<'a> for RemovedComponents<'a, T> {
+    type Target = Self;
+
+    fn borrow(context: &'a Context) -> Result<Self::Target> {
+        let buffers = context
+            .removals()
+            .map(|removals| {
+                removals
+                    .buffers
+                    .read()
+                    .map_err(|_| Error::Borrow(type_name::<Removals>()))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            buffers,
+            last_run: context.last_run(),
+            marker: PhantomData,
+        })
+    }
+}
+
+impl<'a, T: 'static> ComponentBorrow for RemovedComponents<'a, T> {
+    fn borrows() -> Borrows {
+        smallvec![Access::of::<&Removals>()]
+    }
+
+    fn has<U: IntoAccess>() -> bool {
+        Access::of::<&Removals>() == U::access()
+    }
+
+    fn has_dynamic(id: TypeId, exclusive: bool) -> bool {
+        Access::of::<&Removals>().id == id && !exclusive
+    }
+}
+
+impl_into_borrow!(Component, RemovedComponents => RemovedComponentsBorrower);
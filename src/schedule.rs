@@ -5,23 +5,53 @@ use std::{
     ops::{Deref, DerefMut},
 };
 
-use hecs::World;
-use smallvec::SmallVec;
+use hecs::{Component, Entity, World};
+use smallvec::{smallvec, SmallVec};
 
 #[cfg(feature = "parallel")]
 use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
 
 use crate::{
-    borrow::{Borrows, MaybeWrite},
-    Access, CommandBuffer, Context, IntoData, Result, System, SystemName, Write,
+    borrow::{Borrows, ComponentBorrow, ContextBorrow, IntoBorrow, MaybeWrite},
+    dynamic::DynamicAccess,
+    observer::{Observers, TriggerQueue},
+    partition::Partitions,
+    state::{self, StateMap},
+    system::SystemState,
+    Access, CommandBuffer, Context, Error, Hooks, IntoAccess, IntoData, Removals, ResourceTicks,
+    Result, System, SystemName, SystemTicks, Tick, Write,
 };
 
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+/// Decides how commands which failed to apply during a flush are handled
+pub enum FlushPolicy {
+    /// Discard the failures
+    #[default]
+    Ignore,
+    /// Fail the schedule execution with the failed commands
+    Fail,
+    /// Panic with the failures
+    Panic,
+}
+
 #[derive(Default, Debug, Clone)]
 /// Holds information regarding batches
 pub struct BatchInfo<'a> {
     batches: &'a [Batch],
 }
 
+impl<'a> BatchInfo<'a> {
+    /// Returns the number of batches
+    pub fn len(&self) -> usize {
+        self.batches.len()
+    }
+
+    /// Returns true if the schedule has no batches
+    pub fn is_empty(&self) -> bool {
+        self.batches.is_empty()
+    }
+}
+
 impl<'a> Display for BatchInfo<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Batches: ")?;
@@ -41,6 +71,9 @@ impl<'a> Display for BatchInfo<'a> {
 pub struct Batch {
     systems: SmallVec<[DynamicSystem; 8]>,
     has_flush: bool,
+    /// Whether the batch holds conflicting systems which are separated by a
+    /// partition
+    partitioned: bool,
 }
 
 impl Debug for Batch {
@@ -80,31 +113,68 @@ impl DerefMut for Batch {
     }
 }
 
+type BoxedSystemFn = Box<dyn FnMut(&Context) -> Result<bool> + Send>;
+
 // Type erased boxed system
 #[doc(hidden)]
 pub struct DynamicSystem {
-    func: Box<dyn FnMut(&Context) -> Result<()> + Send>,
+    func: BoxedSystemFn,
     name: SystemName,
     borrows: Borrows,
+    state: SystemState,
 }
 
 #[doc(hidden)]
 impl DynamicSystem {
-    fn new<S, Args, Ret>(mut system: S) -> Self
+    pub(crate) fn new<S, Args, Ret>(mut system: S) -> Self
     where
         S: 'static + System<Args, Ret> + Send,
     {
         let borrows = S::borrows();
         let name = system.name();
+        let mut state = SystemState::new();
+        S::init_state(&mut state);
+
         Self {
-            func: Box::new(move |context| system.execute(context)),
+            func: Box::new(move |context| system.run(context)),
             name,
             borrows,
+            state,
         }
     }
 
-    fn execute(&mut self, context: &Context) -> Result<()> {
-        (self.func)(context)
+    /// Returns true if the system borrows `T` immutably
+    pub(crate) fn reads<T: IntoAccess>(&self) -> bool {
+        let id = T::access().id();
+        self.borrows
+            .iter()
+            .any(|access| access.id() == id && !access.exclusive())
+    }
+
+    /// Declares the additional component `access` of the system, which is
+    /// made available to its [`DynamicSubWorld`](crate::DynamicSubWorld)
+    pub(crate) fn with_access(mut self, access: Borrows) -> Self {
+        self.borrows.extend(access.iter().cloned());
+        self.state.insert(DynamicAccess(access));
+        self
+    }
+
+    pub(crate) fn execute(&mut self, context: &Context) -> Result<()> {
+        if let Some(ticks) = context.resource_ticks() {
+            self.state.track_resources(&self.borrows, ticks);
+        }
+
+        let ticks = SystemTicks {
+            last_run: Tick::now(),
+            last_flush: context.flushed(),
+        };
+        let result = (self.func)(&context.with_state(&self.state));
+        // Skipped systems keep comparing against their previous run
+        if !matches!(result, Ok(false)) {
+            self.state.set_ticks(ticks);
+        }
+
+        result.map(|_| ())
     }
 
     /// Get a reference to the dynamic system's name.
@@ -118,6 +188,14 @@ impl DynamicSystem {
 pub struct Schedule {
     batches: Vec<Batch>,
     cmd: CommandBuffer,
+    resource_ticks: ResourceTicks,
+    removals: Removals,
+    /// Whether the flushes record their removals, which is only needed when a
+    /// system reads them
+    records_removals: bool,
+    hooks: Hooks,
+    observers: Observers,
+    partitions: Partitions,
 }
 
 impl Schedule {
@@ -126,11 +204,17 @@ impl Schedule {
         Self {
             batches,
             cmd: Default::default(),
+            resource_ticks: Default::default(),
+            removals: Default::default(),
+            records_removals: true,
+            hooks: Default::default(),
+            observers: Default::default(),
+            partitions: Default::default(),
         }
     }
 
     /// Returns information of how the schedule was split into batches
-    pub fn batch_info(&self) -> BatchInfo {
+    pub fn batch_info(&self) -> BatchInfo<'_> {
         BatchInfo {
             batches: &self.batches,
         }
@@ -148,12 +232,37 @@ impl Schedule {
     pub fn execute_seq<D: IntoData<CommandBuffer>>(&mut self, data: D) -> Result<()> {
         let data = unsafe { data.into_data(&mut self.cmd) };
 
-        let context = Context::new(&data);
-
+        self.removals.update();
+        let triggers = self.observers.queue().clone();
+        let context = Context::new(&data)
+            .with_resource_ticks(&self.resource_ticks)
+            .with_hooks(&self.hooks)
+            .with_triggers(&triggers);
+        let context = match self.records_removals {
+            true => context.with_removals(&self.removals),
+            false => context,
+        };
+
+        // Everything created before the execution has been flushed
+        let mut flushed = Tick::now();
         self.batches.iter_mut().try_for_each(|batch| {
+            Tick::advance();
+            let context = context.with_flushed(flushed);
+            if batch.partitioned {
+                self.partitions.check(&context)?;
+            }
             batch
                 .iter_mut()
-                .try_for_each(|system| system.execute(&context))
+                .try_for_each(|system| system.execute(&context))?;
+
+            if batch.has_flush {
+                flushed = Tick::now();
+                // Values created by observers are flushed later
+                Tick::advance();
+                self.observers.run(&context.with_flushed(flushed))?;
+            }
+
+            Ok(())
         })
     }
 
@@ -165,15 +274,51 @@ impl Schedule {
     pub fn execute<D: IntoData<CommandBuffer> + Send + Sync>(&mut self, data: D) -> Result<()> {
         let data = unsafe { data.into_data(&mut self.cmd) };
 
-        let context = Context::new(&data);
-
+        self.removals.update();
+        let triggers = self.observers.queue().clone();
+        let context = Context::new(&data)
+            .with_resource_ticks(&self.resource_ticks)
+            .with_hooks(&self.hooks)
+            .with_triggers(&triggers);
+        let context = match self.records_removals {
+            true => context.with_removals(&self.removals),
+            false => context,
+        };
+
+        // Everything created before the execution has been flushed
+        let mut flushed = Tick::now();
         self.batches.iter_mut().try_for_each(|batch| {
+            Tick::advance();
+            let context = context.with_flushed(flushed);
+            if batch.partitioned {
+                self.partitions.check(&context)?;
+            }
             batch
                 .par_iter_mut()
-                .try_for_each(|system| system.execute(&context))
+                .try_for_each(|system| system.execute(&context))?;
+
+            if batch.has_flush {
+                flushed = Tick::now();
+                // Values created by observers are flushed later
+                Tick::advance();
+                self.observers.run(&context.with_flushed(flushed))?;
+            }
+
+            Ok(())
         })
     }
 
+    /// Returns the log of removed components, which can be used to record
+    /// removals performed outside the schedule
+    pub fn removals(&self) -> &Removals {
+        &self.removals
+    }
+
+    /// Returns the lifecycle hooks run by the commandbuffer flushes
+    pub fn hooks_mut(&mut self) -> &mut Hooks {
+        &mut self.hooks
+    }
+
     /// Get a reference to the schedule's cmd.
     pub fn cmd(&self) -> &CommandBuffer {
         &self.cmd
@@ -185,12 +330,24 @@ impl Schedule {
     }
 }
 
+/// A step of a [`ScheduleBuilder`], which are split into batches when built
+enum Step {
+    System(DynamicSystem),
+    Flush(DynamicSystem),
+    /// A system which runs alone in its batch, also when appended
+    Isolated(DynamicSystem),
+    Barrier,
+}
+
 #[derive(Default)]
 /// Builder for incrementally constructing a schedule.
 pub struct ScheduleBuilder {
-    batches: Vec<Batch>,
-    current_batch: Batch,
-    current_borrows: HashMap<TypeId, Access>,
+    steps: Vec<Step>,
+    flush_policy: FlushPolicy,
+    hooks: Hooks,
+    observers: Observers,
+    partitions: Partitions,
+    states: StateMap,
 }
 
 impl ScheduleBuilder {
@@ -208,17 +365,15 @@ impl ScheduleBuilder {
         self
     }
 
-    fn add_internal(&mut self, system: DynamicSystem) {
-        // Check borrow
-        let borrows = &system.borrows;
-
-        if !self.check_compatible(borrows) {
-            // Push and create a new batch
-            self.barrier();
-        }
+    pub(crate) fn add_internal(&mut self, system: DynamicSystem) {
+        self.steps.push(Step::System(system))
+    }
 
-        self.add_borrows(borrows);
-        self.current_batch.push(system);
+    /// Adds a system which runs in a batch of its own. Unlike barriers, the
+    /// separation is kept by [`Self::append`].
+    pub(crate) fn add_isolated(&mut self, system: DynamicSystem) -> &mut Self {
+        self.steps.push(Step::Isolated(system));
+        self
     }
 
     /// Append all system from `other` into self, leaving `other` empty.
@@ -226,14 +381,17 @@ impl ScheduleBuilder {
     /// joining them together. Work will be paralellized between the two
     /// schedules.
     pub fn append(&mut self, other: &mut ScheduleBuilder) -> &mut Self {
-        other.barrier();
-
-        other.batches.drain(..).for_each(|mut batch| {
-            batch
-                .systems
+        self.steps.extend(
+            other
+                .steps
                 .drain(..)
-                .for_each(|system| self.add_internal(system))
-        });
+                .filter(|step| !matches!(step, Step::Barrier)),
+        );
+
+        self.hooks.append(&mut other.hooks);
+        self.observers.append(&mut other.observers);
+        self.partitions.append(&mut other.partitions);
+        state::append_states(&mut self.states, &mut other.states);
 
         self
     }
@@ -245,57 +403,241 @@ impl ScheduleBuilder {
     /// creates dependencies, but sometimes a manual dependency is needed for things
     /// such as interior mutability or channels.
     pub fn barrier(&mut self) -> &mut Self {
-        let batch = std::mem::take(&mut self.current_batch);
+        self.steps.push(Step::Barrier);
+        self
+    }
 
-        self.batches.push(batch);
+    /// Flush the commandbuffer and apply the commands to the world
+    pub fn flush(&mut self) -> &mut Self {
+        let policy = self.flush_policy;
+        let system = DynamicSystem::new(
+            (move |world: MaybeWrite<World>, cmd: Write<CommandBuffer>, flush: FlushContext| {
+                flush_system(world, cmd, flush, policy)
+            })
+            .named("flush"),
+        );
+
+        self.steps.push(Step::Flush(system));
+        self
+    }
 
-        self.current_borrows.clear();
+    /// Sets how failed commands are handled by subsequent flushes, including
+    /// the final flush in [`Self::build`]. Failures are ignored by default.
+    pub fn flush_policy(&mut self, policy: FlushPolicy) -> &mut Self {
+        self.flush_policy = policy;
+        self
+    }
 
+    /// Registers a hook which runs when `C` is added to an entity by a flush.
+    /// See [`Hooks`].
+    pub fn on_add<C: Component>(
+        &mut self,
+        hook: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.hooks.on_add::<C>(hook);
         self
     }
 
-    /// Flush the commandbuffer and apply the commands to the world
-    pub fn flush(&mut self) -> &mut Self {
-        self.current_batch.has_flush = true;
-        self.add_system(flush_system)
+    /// Registers a hook which runs when `C` is added to or replaced in an
+    /// entity by a flush. See [`Hooks`].
+    pub fn on_insert<C: Component>(
+        &mut self,
+        hook: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.hooks.on_insert::<C>(hook);
+        self
     }
 
-    fn add_borrows(&mut self, borrows: &Borrows) {
-        self.current_borrows
-            .extend(borrows.into_iter().map(|val| (val.id(), *val)))
+    /// Registers a hook which runs before `C` is removed from an entity by a
+    /// flush. See [`Hooks`].
+    pub fn on_remove<C: Component>(
+        &mut self,
+        hook: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.hooks.on_remove::<C>(hook);
+        self
     }
 
-    /// Returns true if no borrows conflict with the current ones
-    fn check_compatible(&self, borrows: &Borrows) -> bool {
-        for borrow in borrows {
-            // Type is already borrowed
-            if let Some(curr) = self.current_borrows.get(&borrow.id()) {
-                // Already exclusively borrowed or new borrow is exlcusive
-                if curr.exclusive() || borrow.exclusive() {
-                    return false;
-                }
-            }
-        }
+    pub(crate) fn observers_mut(&mut self) -> &mut Observers {
+        &mut self.observers
+    }
 
-        true
+    pub(crate) fn partitions_mut(&mut self) -> &mut Partitions {
+        &mut self.partitions
+    }
+
+    pub(crate) fn states_mut(&mut self) -> &mut StateMap {
+        &mut self.states
     }
 
     /// FLushes the commandbuffer and builds the schedule.
+    ///
+    /// The systems are split into batches of compatible borrows, which
+    /// accounts for every declared partition.
     pub fn build(&mut self) -> Schedule {
         self.flush();
-        // Push the current batch
-        self.barrier();
 
         let builder = std::mem::take(self);
 
-        Schedule::new(builder.batches)
+        // Recording the removals of a flush requires capturing the components
+        // of the affected entities beforehand, which is skipped unless read
+        let reads_removals = |system: &DynamicSystem| system.reads::<&Removals>();
+        let records_removals = builder.steps.iter().any(|step| match step {
+            Step::System(system) | Step::Flush(system) | Step::Isolated(system) => {
+                reads_removals(system)
+            }
+            Step::Barrier => false,
+        }) || builder.observers.systems().any(reads_removals)
+            || builder
+                .states
+                .values()
+                .any(|systems| systems.any_system(&reads_removals));
+
+        let mut batcher = Batcher {
+            partitions: &builder.partitions,
+            batches: Vec::new(),
+            current: Batch::default(),
+            borrows: HashMap::new(),
+        };
+
+        for step in builder.steps {
+            match step {
+                Step::System(system) => batcher.add(system),
+                Step::Flush(system) => {
+                    // The flush may start a new batch, which is then the one
+                    // flushing
+                    batcher.add(system);
+                    batcher.current.has_flush = true;
+                }
+                Step::Isolated(system) => {
+                    batcher.barrier();
+                    batcher.add(system);
+                    batcher.barrier();
+                }
+                Step::Barrier => batcher.barrier(),
+            }
+        }
+
+        // Push the current batch
+        batcher.barrier();
+
+        let mut schedule = Schedule::new(batcher.batches);
+        schedule.hooks = builder.hooks;
+        builder.observers.register(&mut schedule.hooks);
+        schedule.observers = builder.observers;
+        schedule.partitions = builder.partitions;
+        schedule.records_removals = records_removals;
+        schedule
+    }
+}
+
+/// Splits the systems of a schedule into batches
+struct Batcher<'a> {
+    partitions: &'a Partitions,
+    batches: Vec<Batch>,
+    current: Batch,
+    borrows: HashMap<TypeId, Vec<Access>>,
+}
+
+impl<'a> Batcher<'a> {
+    fn add(&mut self, system: DynamicSystem) {
+        match self.check_compatible(&system.borrows) {
+            Some(partitioned) => self.current.partitioned |= partitioned,
+            // Push and create a new batch
+            None => self.barrier(),
+        }
+
+        for borrow in &system.borrows {
+            self.borrows.entry(borrow.id()).or_default().push(*borrow)
+        }
+
+        self.current.push(system);
+    }
+
+    fn barrier(&mut self) {
+        self.batches.push(std::mem::take(&mut self.current));
+        self.borrows.clear();
+    }
+
+    /// Returns None if the borrows conflict with the current ones, or
+    /// whether a partition is required to tell them apart
+    fn check_compatible(&self, borrows: &Borrows) -> Option<bool> {
+        let mut partitioned = false;
+        for borrow in borrows {
+            // Type is already borrowed, either exclusively or the new borrow
+            // is exclusive, by a system which may access the same entities
+            if let Some(curr) = self.borrows.get(&borrow.id()) {
+                for curr in curr.iter().filter(|curr| curr.conflicts(borrow)) {
+                    if !self.partitions.separates(curr, borrow) {
+                        return None;
+                    }
+
+                    partitioned = true;
+                }
+            }
+        }
+
+        Some(partitioned)
     }
 }
 
 // Flushes the commandbuffer
-fn flush_system(mut world: MaybeWrite<World>, mut cmd: Write<CommandBuffer>) -> Result<()> {
+fn flush_system(
+    mut world: MaybeWrite<World>,
+    mut cmd: Write<CommandBuffer>,
+    flush: FlushContext,
+    policy: FlushPolicy,
+) -> Result<()> {
     if let Some(world) = world.option_mut() {
-        cmd.execute(world);
+        let result = cmd.flush(world, flush.removals, flush.hooks, flush.triggers);
+
+        match (result, policy) {
+            (Ok(()), _) | (Err(_), FlushPolicy::Ignore) => {}
+            (Err(e), FlushPolicy::Fail) => return Err(Error::Commands(e)),
+            (Err(e), FlushPolicy::Panic) => panic!("Failed to flush commandbuffer: {e:#?}"),
+        }
     }
     Ok(())
 }
+
+#[doc(hidden)]
+/// Provides the removal log, lifecycle hooks and trigger queue to a
+/// commandbuffer flush
+pub struct FlushContext<'a> {
+    removals: Option<&'a Removals>,
+    hooks: Option<&'a Hooks>,
+    triggers: Option<&'a TriggerQueue>,
+}
+
+#[doc(hidden)]
+pub struct FlushContextBorrower;
+
+impl IntoBorrow for FlushContext<'_> {
+    type Borrow = FlushContextBorrower;
+}
+
+impl<'a> ContextBorrow<'a> for FlushContextBorrower {
+    type Target = FlushContext<'a>;
+
+    fn borrow(context: &'a Context) -> Result<Self::Target> {
+        Ok(FlushContext {
+            removals: context.removals(),
+            hooks: context.hooks(),
+            triggers: context.triggers(),
+        })
+    }
+}
+
+impl ComponentBorrow for FlushContext<'_> {
+    fn borrows() -> Borrows {
+        smallvec![Access::of::<&mut Removals>()]
+    }
+
+    fn has<U: IntoAccess>() -> bool {
+        Access::of::<&mut Removals>().id == U::access().id
+    }
+
+    fn has_dynamic(id: TypeId, _: bool) -> bool {
+        Access::of::<&mut Removals>().id == id
+    }
+}
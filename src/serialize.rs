@@ -0,0 +1,530 @@
+//! Provides a serializable representation of commands which can be recorded
+//! in one process and applied to a world in another, such as for networking
+//! or replays.
+//!
+//! Components are identified by stable names registered in a
+//! [`ComponentRegistry`]. The registry is used both to serialize a
+//! [`SerializableCommandBuffer`] and to deserialize it into a regular
+//! [`CommandBuffer`] which is applied with [`CommandBuffer::execute`].
+//!
+//! Entities are sent as is, which means the receiving world is expected to
+//! mirror the entities of the sender, E.g; by spawning them with
+//! [`SerializableCommandBuffer::spawn_at`].
+use std::{
+    any::{type_name, TypeId},
+    collections::HashMap,
+    fmt,
+};
+
+use hecs::{Component, Entity, EntityBuilder};
+use serde::{
+    de::{DeserializeOwned, DeserializeSeed, Error as _, MapAccess, SeqAccess, Visitor},
+    ser::{Error as _, SerializeMap, SerializeSeq},
+    Deserializer, Serialize, Serializer,
+};
+
+use crate::{commandbuffer::RemoveCommand, CommandBuffer, CommandKind, GenericWorld};
+
+type DeserializeFn =
+    fn(&mut dyn erased_serde::Deserializer, &mut EntityBuilder) -> erased_serde::Result<()>;
+
+struct Registration {
+    name: String,
+    deserialize: DeserializeFn,
+    remove: RemoveCommand,
+}
+
+#[derive(Default)]
+/// Maps stable component names to the functions used to serialize and
+/// deserialize them.
+///
+/// The same names need to be registered on both ends.
+pub struct ComponentRegistry {
+    registrations: Vec<Registration>,
+    by_name: HashMap<String, usize>,
+    by_id: HashMap<TypeId, usize>,
+}
+
+impl ComponentRegistry {
+    /// Creates a new empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the component `C` by a stable `name`.
+    ///
+    /// # Panics
+    /// Panics if `C` or `name` is already registered
+    pub fn register<C: Component + Serialize + DeserializeOwned>(
+        &mut self,
+        name: impl Into<String>,
+    ) -> &mut Self {
+        let name = name.into();
+        let index = self.registrations.len();
+
+        assert!(
+            self.by_id.insert(TypeId::of::<C>(), index).is_none(),
+            "Component {:?} is already registered",
+            type_name::<C>()
+        );
+        assert!(
+            self.by_name.insert(name.clone(), index).is_none(),
+            "Component name {name:?} is already registered"
+        );
+
+        self.registrations.push(Registration {
+            name,
+            deserialize: |deserializer, builder| {
+                builder.add(erased_serde::deserialize::<C>(deserializer)?);
+                Ok(())
+            },
+            remove: RemoveCommand::one::<C>(),
+        });
+
+        self
+    }
+
+    /// Returns the registered name of `C`
+    pub fn name_of<C: Component>(&self) -> Option<&str> {
+        self.name_of_id(TypeId::of::<C>())
+    }
+
+    fn name_of_id(&self, id: TypeId) -> Option<&str> {
+        self.by_id
+            .get(&id)
+            .map(|&index| self.registrations[index].name.as_str())
+    }
+
+    fn get(&self, name: &str) -> Option<&Registration> {
+        self.by_name
+            .get(name)
+            .map(|&index| &self.registrations[index])
+    }
+
+    /// Returns a serializable view of `commands` using the registered names
+    pub fn serialize<'a>(&'a self, commands: &'a SerializableCommandBuffer) -> impl Serialize + 'a {
+        SerializeCommands {
+            registry: self,
+            commands,
+        }
+    }
+
+    /// Deserializes commands serialized by [`Self::serialize`] into a
+    /// commandbuffer which can be applied to a world.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        &self,
+        deserializer: D,
+    ) -> Result<CommandBuffer, D::Error> {
+        CommandsSeed { registry: self }.deserialize(deserializer)
+    }
+}
+
+/// A component which can be recorded in a [`SerializableCommandBuffer`]
+pub trait SerializableComponent: erased_serde::Serialize + Component {
+    #[doc(hidden)]
+    fn component_id(&self) -> TypeId;
+    #[doc(hidden)]
+    fn as_serialize(&self) -> &dyn erased_serde::Serialize;
+    #[doc(hidden)]
+    fn add_to(self: Box<Self>, builder: &mut EntityBuilder);
+}
+
+impl<C: Component + Serialize> SerializableComponent for C {
+    fn component_id(&self) -> TypeId {
+        TypeId::of::<C>()
+    }
+
+    fn as_serialize(&self) -> &dyn erased_serde::Serialize {
+        self
+    }
+
+    fn add_to(self: Box<Self>, builder: &mut EntityBuilder) {
+        builder.add(*self);
+    }
+}
+
+/// A tuple of serializable components
+pub trait SerializableBundle {
+    /// Converts the bundle into type erased components
+    fn into_components(self) -> Vec<Box<dyn SerializableComponent>>;
+}
+
+macro_rules! tuple_impl {
+    ($($name: ident),*) => {
+        impl<$($name: Component + Serialize),*> SerializableBundle for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn into_components(self) -> Vec<Box<dyn SerializableComponent>> {
+                let ($($name,)*) = self;
+                vec![$(Box::new($name)),*]
+            }
+        }
+    };
+}
+
+impl_for_tuples!(tuple_impl);
+
+enum SerializableCommand {
+    Spawn(Option<Entity>, Vec<Box<dyn SerializableComponent>>),
+    Insert(Entity, Vec<Box<dyn SerializableComponent>>),
+    Remove(Entity, Vec<TypeId>),
+    Despawn(Entity),
+}
+
+#[derive(Default)]
+/// Records spawn, insert, remove and despawn commands of registered
+/// components in a form which can be serialized with a [`ComponentRegistry`].
+///
+/// Use [`Self::into_command_buffer`] to apply the commands locally.
+pub struct SerializableCommandBuffer {
+    commands: Vec<SerializableCommand>,
+}
+
+impl SerializableCommandBuffer {
+    /// Creates a new empty commandbuffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawns a new entity with components
+    pub fn spawn(&mut self, components: impl SerializableBundle) {
+        self.commands.push(SerializableCommand::Spawn(
+            None,
+            components.into_components(),
+        ))
+    }
+
+    /// Spawns an entity with components using a specific handle. See
+    /// [`CommandBuffer::spawn_at`]
+    pub fn spawn_at(&mut self, entity: Entity, components: impl SerializableBundle) {
+        self.commands.push(SerializableCommand::Spawn(
+            Some(entity),
+            components.into_components(),
+        ))
+    }
+
+    /// Reserves an entity from `world` and spawns it with components when the
+    /// commands are executed.
+    pub fn spawn_reserved(
+        &mut self,
+        world: &impl GenericWorld,
+        components: impl SerializableBundle,
+    ) -> Entity {
+        let entity = world.reserve();
+        self.spawn_at(entity, components);
+        entity
+    }
+
+    /// Inserts components into an already existing entity
+    pub fn insert(&mut self, entity: Entity, components: impl SerializableBundle) {
+        self.commands.push(SerializableCommand::Insert(
+            entity,
+            components.into_components(),
+        ))
+    }
+
+    /// Inserts a single component into an already existing entity
+    pub fn insert_one(&mut self, entity: Entity, component: impl Component + Serialize) {
+        self.insert(entity, (component,))
+    }
+
+    /// Remove a single component from an entity
+    pub fn remove_one<C: Component>(&mut self, entity: Entity) {
+        self.commands
+            .push(SerializableCommand::Remove(entity, vec![TypeId::of::<C>()]))
+    }
+
+    /// Despawn an entity from the world
+    pub fn despawn(&mut self, entity: Entity) {
+        self.commands.push(SerializableCommand::Despawn(entity))
+    }
+
+    /// Returns true if no commands are recorded
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Drop all recorded commands
+    pub fn clear(&mut self) {
+        self.commands.clear()
+    }
+
+    /// Converts the recorded commands into a regular commandbuffer, which
+    /// applies them without serializing. Removals of components not registered
+    /// in `registry` are dropped.
+    pub fn into_command_buffer(self, registry: &ComponentRegistry) -> CommandBuffer {
+        let mut cmd = CommandBuffer::new();
+
+        let build = |components: Vec<Box<dyn SerializableComponent>>| {
+            let mut builder = EntityBuilder::new();
+            components
+                .into_iter()
+                .for_each(|component| component.add_to(&mut builder));
+            builder
+        };
+
+        for command in self.commands {
+            match command {
+                SerializableCommand::Spawn(None, components) => {
+                    cmd.spawn(build(components).build())
+                }
+                SerializableCommand::Spawn(Some(entity), components) => {
+                    cmd.spawn_at(entity, build(components).build())
+                }
+                SerializableCommand::Insert(entity, components) => {
+                    cmd.insert(entity, build(components).build())
+                }
+                SerializableCommand::Remove(entity, ids) => ids
+                    .iter()
+                    .filter_map(|id| registry.by_id.get(id))
+                    .for_each(|&index| {
+                        cmd.remove_dynamic(entity, registry.registrations[index].remove)
+                    }),
+                SerializableCommand::Despawn(entity) => cmd.despawn(entity),
+            }
+        }
+
+        cmd
+    }
+}
+
+struct SerializeCommands<'a> {
+    registry: &'a ComponentRegistry,
+    commands: &'a SerializableCommandBuffer,
+}
+
+impl Serialize for SerializeCommands<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let commands = &self.commands.commands;
+        let mut seq = serializer.serialize_seq(Some(commands.len()))?;
+        for command in commands {
+            seq.serialize_element(&SerializeCommand {
+                registry: self.registry,
+                command,
+            })?;
+        }
+        seq.end()
+    }
+}
+
+struct SerializeCommand<'a> {
+    registry: &'a ComponentRegistry,
+    command: &'a SerializableCommand,
+}
+
+impl Serialize for SerializeCommand<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let registry = self.registry;
+        let components = |components| SerializeComponents {
+            registry,
+            components,
+        };
+
+        match self.command {
+            SerializableCommand::Spawn(entity, c) => {
+                let mut seq = serializer.serialize_seq(Some(3))?;
+                seq.serialize_element(&CommandKind::Spawn)?;
+                seq.serialize_element(entity)?;
+                seq.serialize_element(&components(c))?;
+                seq.end()
+            }
+            SerializableCommand::Insert(entity, c) => {
+                let mut seq = serializer.serialize_seq(Some(3))?;
+                seq.serialize_element(&CommandKind::Insert)?;
+                seq.serialize_element(entity)?;
+                seq.serialize_element(&components(c))?;
+                seq.end()
+            }
+            SerializableCommand::Remove(entity, ids) => {
+                let names = ids
+                    .iter()
+                    .map(|&id| {
+                        registry.name_of_id(id).ok_or_else(|| {
+                            S::Error::custom("Attempt to remove unregistered component")
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let mut seq = serializer.serialize_seq(Some(3))?;
+                seq.serialize_element(&CommandKind::Remove)?;
+                seq.serialize_element(entity)?;
+                seq.serialize_element(&names)?;
+                seq.end()
+            }
+            SerializableCommand::Despawn(entity) => {
+                let mut seq = serializer.serialize_seq(Some(2))?;
+                seq.serialize_element(&CommandKind::Despawn)?;
+                seq.serialize_element(entity)?;
+                seq.end()
+            }
+        }
+    }
+}
+
+struct SerializeComponents<'a> {
+    registry: &'a ComponentRegistry,
+    components: &'a [Box<dyn SerializableComponent>],
+}
+
+impl Serialize for SerializeComponents<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.components.len()))?;
+        for component in self.components {
+            let name = self
+                .registry
+                .name_of_id(component.component_id())
+                .ok_or_else(|| S::Error::custom("Attempt to serialize unregistered component"))?;
+
+            map.serialize_entry(name, component.as_serialize())?;
+        }
+        map.end()
+    }
+}
+
+struct CommandsSeed<'a> {
+    registry: &'a ComponentRegistry,
+}
+
+impl<'de> DeserializeSeed<'de> for CommandsSeed<'_> {
+    type Value = CommandBuffer;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for CommandsSeed<'_> {
+    type Value = CommandBuffer;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sequence of commands")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut cmd = CommandBuffer::new();
+        while seq
+            .next_element_seed(CommandSeed {
+                registry: self.registry,
+                cmd: &mut cmd,
+            })?
+            .is_some()
+        {}
+
+        Ok(cmd)
+    }
+}
+
+struct CommandSeed<'a> {
+    registry: &'a ComponentRegistry,
+    cmd: &'a mut CommandBuffer,
+}
+
+impl<'de> DeserializeSeed<'de> for CommandSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for CommandSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a command")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        fn next<'de, A: SeqAccess<'de>, T: serde::Deserialize<'de>>(
+            seq: &mut A,
+            index: usize,
+        ) -> Result<T, A::Error> {
+            seq.next_element()?
+                .ok_or_else(|| A::Error::invalid_length(index, &"a command"))
+        }
+
+        let components = |seq: &mut A| {
+            seq.next_element_seed(ComponentsSeed {
+                registry: self.registry,
+            })?
+            .ok_or_else(|| A::Error::invalid_length(2, &"a command"))
+        };
+
+        match next::<_, CommandKind>(&mut seq, 0)? {
+            CommandKind::Spawn => match next::<_, Option<Entity>>(&mut seq, 1)? {
+                Some(entity) => self.cmd.spawn_at(entity, components(&mut seq)?.build()),
+                None => self.cmd.spawn(components(&mut seq)?.build()),
+            },
+            CommandKind::Insert => {
+                let entity = next(&mut seq, 1)?;
+                self.cmd.insert(entity, components(&mut seq)?.build())
+            }
+            CommandKind::Remove => {
+                let entity = next(&mut seq, 1)?;
+                for name in next::<_, Vec<String>>(&mut seq, 2)? {
+                    let registration = self.registry.get(&name).ok_or_else(|| {
+                        A::Error::custom(format!("Unregistered component {name:?}"))
+                    })?;
+
+                    self.cmd.remove_dynamic(entity, registration.remove);
+                }
+            }
+            CommandKind::Despawn => self.cmd.despawn(next(&mut seq, 1)?),
+            CommandKind::Write => {
+                return Err(A::Error::custom("Custom writes can not be deserialized"))
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct ComponentsSeed<'a> {
+    registry: &'a ComponentRegistry,
+}
+
+impl<'de> DeserializeSeed<'de> for ComponentsSeed<'_> {
+    type Value = EntityBuilder;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ComponentsSeed<'_> {
+    type Value = EntityBuilder;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of components")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut builder = EntityBuilder::new();
+        while let Some(name) = map.next_key::<String>()? {
+            let registration = self
+                .registry
+                .get(&name)
+                .ok_or_else(|| A::Error::custom(format!("Unregistered component {name:?}")))?;
+
+            map.next_value_seed(ComponentSeed {
+                registration,
+                builder: &mut builder,
+            })?;
+        }
+
+        Ok(builder)
+    }
+}
+
+struct ComponentSeed<'a> {
+    registration: &'a Registration,
+    builder: &'a mut EntityBuilder,
+}
+
+impl<'de> DeserializeSeed<'de> for ComponentSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.registration.deserialize)(&mut deserializer, self.builder).map_err(D::Error::custom)
+    }
+}
@@ -0,0 +1,322 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use hecs::Component;
+
+use crate::{
+    borrow::{Borrows, ComponentBorrow, ContextBorrow, MaybeWrite},
+    schedule::DynamicSystem,
+    Condition, Context, Read, Result, ScheduleBuilder, System, SystemName, Write,
+};
+
+/// A value which selects the systems of a [`State`]
+pub trait StateValue: Component + Clone + PartialEq {}
+
+impl<S: Component + Clone + PartialEq> StateValue for S {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Resource holding the current state of a state machine, such as the flow of
+/// a game.
+///
+/// The state is changed by requesting a transition through [`NextState`],
+/// which is applied at the point of the schedule given by
+/// [`ScheduleBuilder::add_state`].
+pub struct State<S> {
+    current: S,
+}
+
+impl<S> State<S> {
+    /// Creates a state machine in the state `initial`
+    pub fn new(initial: S) -> Self {
+        Self { current: initial }
+    }
+
+    /// Returns the current state
+    pub fn get(&self) -> &S {
+        &self.current
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Resource used to request a transition of [`State<S>`]
+pub struct NextState<S> {
+    next: Option<S>,
+}
+
+impl<S> Default for NextState<S> {
+    fn default() -> Self {
+        Self { next: None }
+    }
+}
+
+impl<S> NextState<S> {
+    /// Creates a resource without a pending transition
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests a transition to `next`, replacing any pending request
+    pub fn set(&mut self, next: S) {
+        self.next = Some(next)
+    }
+
+    /// Returns the pending transition, if any
+    pub fn get(&self) -> Option<&S> {
+        self.next.as_ref()
+    }
+}
+
+/// The systems of each state
+struct StateSystems<S> {
+    on_enter: Vec<(S, DynamicSystem)>,
+    on_exit: Vec<(S, DynamicSystem)>,
+    on_update: Vec<(S, DynamicSystem)>,
+    entered: bool,
+    /// The systems these were moved into by [`ScheduleBuilder::append`]
+    merged: Option<SharedStateSystems<S>>,
+}
+
+impl<S> Default for StateSystems<S> {
+    fn default() -> Self {
+        Self {
+            on_enter: Vec::new(),
+            on_exit: Vec::new(),
+            on_update: Vec::new(),
+            entered: false,
+            merged: None,
+        }
+    }
+}
+
+/// Returns the systems which `systems` were merged into, if any
+fn resolve<S>(systems: &SharedStateSystems<S>) -> SharedStateSystems<S> {
+    let mut systems = systems.clone();
+    loop {
+        let merged = systems.lock().unwrap().merged.clone();
+        match merged {
+            Some(merged) => systems = merged,
+            None => return systems,
+        }
+    }
+}
+
+fn run<S: PartialEq>(
+    systems: &mut [(S, DynamicSystem)],
+    state: &S,
+    context: &Context,
+) -> Result<()> {
+    systems
+        .iter_mut()
+        .filter(|(s, _)| s == state)
+        .try_for_each(|(_, system)| system.execute(context))
+}
+
+type SharedStateSystems<S> = Arc<Mutex<StateSystems<S>>>;
+
+/// Applies the transitions of `State<S>` and runs the systems of the current
+/// state
+struct StateTransition<S> {
+    systems: SharedStateSystems<S>,
+}
+
+impl<S: StateValue> System<(), ()> for StateTransition<S> {
+    fn execute(&mut self, context: &Context) -> Result<()> {
+        let systems = resolve(&self.systems);
+        let mut systems = systems.lock().unwrap();
+        let systems = &mut *systems;
+
+        let current = Read::<State<S>>::borrow(context)?.current.clone();
+        if !systems.entered {
+            systems.entered = true;
+            run(&mut systems.on_enter, &current, context)?;
+        }
+
+        let next = MaybeWrite::<NextState<S>>::borrow(context)?
+            .option_mut()
+            .and_then(|next| next.next.take());
+
+        let current = match next {
+            Some(next) if next != current => {
+                run(&mut systems.on_exit, &current, context)?;
+                Write::<State<S>>::borrow(context)?.current = next.clone();
+                run(&mut systems.on_enter, &next, context)?;
+                next
+            }
+            _ => current,
+        };
+
+        run(&mut systems.on_update, &current, context)
+    }
+
+    fn name(&self) -> SystemName {
+        format!("transition {}", type_name::<S>()).into()
+    }
+
+    // The systems of the states may borrow anything, which is accounted for
+    // by running the transition in a batch of its own
+    fn borrows() -> Borrows {
+        Write::<State<S>>::borrows()
+            .into_iter()
+            .chain(MaybeWrite::<NextState<S>>::borrows())
+            .collect()
+    }
+}
+
+impl ScheduleBuilder {
+    /// Adds the transition point of [`State<S>`].
+    ///
+    /// Each execution, a transition requested through [`NextState<S>`] runs
+    /// the `on_exit` systems of the current state, changes the state, and runs
+    /// the `on_enter` systems of the new state. The `on_update` systems of the
+    /// current state run afterwards. The `on_enter` systems of the initial
+    /// state run at the first execution.
+    ///
+    /// The systems of the states run sequentially, in a batch of their own,
+    /// since they may borrow anything. This holds also when the builder is
+    /// appended to another.
+    pub fn add_state<S: StateValue>(&mut self) -> &mut Self {
+        let systems = self.state_systems::<S>();
+        self.add_isolated(DynamicSystem::new(StateTransition { systems }))
+    }
+
+    /// Adds a system which runs when [`State<S>`] transitions to `state`
+    pub fn on_enter<S: StateValue, Args, Ret, F>(&mut self, state: S, system: F) -> &mut Self
+    where
+        F: 'static + System<Args, Ret> + Send,
+    {
+        self.state_systems::<S>()
+            .lock()
+            .unwrap()
+            .on_enter
+            .push((state, DynamicSystem::new(system)));
+        self
+    }
+
+    /// Adds a system which runs when [`State<S>`] transitions from `state`
+    pub fn on_exit<S: StateValue, Args, Ret, F>(&mut self, state: S, system: F) -> &mut Self
+    where
+        F: 'static + System<Args, Ret> + Send,
+    {
+        self.state_systems::<S>()
+            .lock()
+            .unwrap()
+            .on_exit
+            .push((state, DynamicSystem::new(system)));
+        self
+    }
+
+    /// Adds a system which runs at the transition point of [`State<S>`] while
+    /// in `state`
+    pub fn on_update<S: StateValue, Args, Ret, F>(&mut self, state: S, system: F) -> &mut Self
+    where
+        F: 'static + System<Args, Ret> + Send,
+    {
+        self.state_systems::<S>()
+            .lock()
+            .unwrap()
+            .on_update
+            .push((state, DynamicSystem::new(system)));
+        self
+    }
+
+    fn state_systems<S: StateValue>(&mut self) -> SharedStateSystems<S> {
+        self.states_mut()
+            .entry(TypeId::of::<S>())
+            .or_insert_with(|| Box::new(SharedStateSystems::<S>::default()))
+            .as_any()
+            .downcast_ref::<SharedStateSystems<S>>()
+            .expect("State type mismatch")
+            .clone()
+    }
+}
+
+/// A [`Condition`] which holds while [`State<S>`] is `state`
+pub struct InState<S> {
+    state: S,
+}
+
+/// Returns a condition which holds while [`State<S>`] is `state`, for use
+/// with [`System::run_if`]
+pub fn in_state<S: StateValue>(state: S) -> InState<S> {
+    InState { state }
+}
+
+impl<S: StateValue> Condition for InState<S> {
+    fn evaluate(&mut self, context: &Context) -> Result<bool> {
+        Ok(Read::<State<S>>::borrow(context)?.current == self.state)
+    }
+
+    fn borrows() -> Borrows {
+        Read::<State<S>>::borrows()
+    }
+}
+
+/// Type erased [`SharedStateSystems`]
+pub(crate) trait AnyStateSystems: Send {
+    fn as_any(&self) -> &dyn Any;
+
+    /// Moves the systems of `other`, which must be of the same state type,
+    /// into self
+    fn append(&self, other: Box<dyn AnyStateSystems>);
+
+    /// Returns true if `f` holds for any of the systems
+    fn any_system(&self, f: &dyn Fn(&DynamicSystem) -> bool) -> bool;
+}
+
+impl<S: StateValue> AnyStateSystems for SharedStateSystems<S> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn append(&self, other: Box<dyn AnyStateSystems>) {
+        let other = other
+            .as_any()
+            .downcast_ref::<SharedStateSystems<S>>()
+            .expect("State type mismatch")
+            .clone();
+
+        let (target, other) = (resolve(self), resolve(&other));
+        if Arc::ptr_eq(&target, &other) {
+            return;
+        }
+
+        let mut systems = target.lock().unwrap();
+        let mut other = other.lock().unwrap();
+        systems.on_enter.append(&mut other.on_enter);
+        systems.on_exit.append(&mut other.on_exit);
+        systems.on_update.append(&mut other.on_update);
+
+        // Transition points added to the other builder run the merged systems
+        other.merged = Some(target.clone());
+    }
+
+    fn any_system(&self, f: &dyn Fn(&DynamicSystem) -> bool) -> bool {
+        let systems = resolve(self);
+        let systems = systems.lock().unwrap();
+        let found = systems
+            .on_enter
+            .iter()
+            .chain(&systems.on_exit)
+            .chain(&systems.on_update)
+            .any(|(_, system)| f(system));
+        found
+    }
+}
+
+/// The systems of each state machine, keyed by the type of the state
+pub(crate) type StateMap = HashMap<TypeId, Box<dyn AnyStateSystems>>;
+
+/// Merges the state systems of `other` into `states`
+pub(crate) fn append_states(states: &mut StateMap, other: &mut StateMap) {
+    for (ty, systems) in other.drain() {
+        match states.get(&ty) {
+            Some(existing) => existing.append(systems),
+            None => {
+                states.insert(ty, systems);
+            }
+        }
+    }
+}
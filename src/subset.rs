@@ -0,0 +1,110 @@
+use hecs::{Satisfies, With, Without};
+
+use crate::AllAccess;
+
+/// Proves at compile time that the query `Self` only accesses components
+/// declared by the subworld access `T`.
+///
+/// `I` locates the accessed components in `T`, and is always inferred. It is
+/// implemented for references, [`Option`], [`With`], [`Without`], [`Satisfies`],
+/// and tuples of these.
+///
+/// Derived and dynamic queries are checked at runtime through
+/// [`SubWorldRaw::query`](crate::SubWorldRaw::query) instead.
+///
+/// ```
+/// use hecs::World;
+/// use hecs_schedule::SubWorldRef;
+///
+/// let world = World::default();
+/// let subworld = SubWorldRef::<(&i32, &mut f32)>::new(&world);
+/// subworld.query_static::<(&i32, &f32), _>();
+/// subworld.query_static::<(&mut f32, Option<&i32>), _>();
+/// ```
+///
+/// ```compile_fail
+/// use hecs::World;
+/// use hecs_schedule::SubWorldRef;
+///
+/// let world = World::default();
+/// let subworld = SubWorldRef::<(&i32, &mut f32)>::new(&world);
+/// subworld.query_static::<&mut i32, _>();
+/// ```
+pub trait SubsetOf<T, I> {}
+
+/// Proves that the access `Self` allows reading the component `C`, located by
+/// `I`.
+pub trait HasRead<C, I> {}
+
+/// Proves that the access `Self` allows writing the component `C`, located by
+/// `I`.
+pub trait HasWrite<C, I> {}
+
+#[doc(hidden)]
+/// Locates a component at position `N` of a tuple
+pub struct At<const N: usize>;
+
+#[doc(hidden)]
+/// Locates a component which is borrowed immutably
+pub struct Shared<I>(I);
+
+#[doc(hidden)]
+/// Locates a component which is borrowed mutably
+pub struct Exclusive<I>(I);
+
+#[doc(hidden)]
+/// Locates any component of [`AllAccess`]
+pub struct Everything;
+
+impl<C> HasRead<C, Everything> for AllAccess {}
+impl<C> HasWrite<C, Everything> for AllAccess {}
+
+impl<C> HasRead<C, Shared<At<0>>> for &C {}
+impl<C> HasRead<C, Exclusive<At<0>>> for &mut C {}
+impl<C> HasWrite<C, At<0>> for &mut C {}
+
+impl<C, T: HasRead<C, I>, I> SubsetOf<T, I> for &C {}
+impl<C, T: HasWrite<C, I>, I> SubsetOf<T, I> for &mut C {}
+impl<Q: SubsetOf<T, I>, T, I> SubsetOf<T, I> for Option<Q> {}
+impl<Q: SubsetOf<T, I>, R, T, I> SubsetOf<T, I> for With<Q, R> {}
+impl<Q: SubsetOf<T, I>, R, T, I> SubsetOf<T, I> for Without<Q, R> {}
+impl<Q, T> SubsetOf<T, ()> for Satisfies<Q> {}
+impl<T> SubsetOf<T, ()> for () {}
+
+/// Implements the access traits for each position of a tuple
+macro_rules! positions {
+    ([$($before: ident),*] []) => {};
+    ([$($before: ident),*] [[$idx: tt => $current: ident] $(, [$aidx: tt => $after: ident])*]) => {
+        impl<'a, Comp, $($before,)* $($after,)*> HasRead<Comp, Shared<At<$idx>>>
+            for ($($before,)* &'a Comp, $($after,)*) {}
+        impl<'a, Comp, $($before,)* $($after,)*> HasRead<Comp, Exclusive<At<$idx>>>
+            for ($($before,)* &'a mut Comp, $($after,)*) {}
+        impl<'a, Comp, $($before,)* $($after,)*> HasWrite<Comp, At<$idx>>
+            for ($($before,)* &'a mut Comp, $($after,)*) {}
+
+        positions!([$($before,)* $current] [$([$aidx => $after]),*]);
+    };
+}
+
+macro_rules! tuple_impl {
+    ($([$idx: tt => $name: ident => $index: ident]),*) => {
+        positions!([] [$([$idx => $name]),*]);
+
+        impl<T, $($name: SubsetOf<T, $index>, $index),*> SubsetOf<T, ($($index,)*)>
+            for ($($name,)*) {}
+    };
+}
+
+crate::expand!(tuple_impl,
+    [0 => L => IL],
+    [1 => K => IK],
+    [2 => J => IJ],
+    [3 => I => II],
+    [4 => H => IH],
+    [5 => G => IG],
+    [6 => F => IF],
+    [7 => E => IE],
+    [8 => D => ID],
+    [9 => C => IC],
+    [10 => B => IB],
+    [11 => A => IA]);
@@ -1,18 +1,24 @@
 use atomic_refcell::AtomicRef;
 use std::{any::type_name, marker::PhantomData, ops::Deref};
 
-use crate::{access::*, borrow::ComponentBorrow, Error, Result};
+use crate::{
+    access::*,
+    borrow::ComponentBorrow,
+    change::{Filter, FilteredQuery, SystemTicks, Tick},
+    filter::ArchetypeFilter,
+    Error, HasRead, HasWrite, Result, SubsetOf,
+};
 
-use crate::{GenericWorld, QueryOne};
+use crate::QueryOne;
 use hecs::{Component, Entity, Query, QueryBorrow, World};
 
 /// Type alias for a subworld referencing the world by an [atomic_refcell::AtomicRef]. Most
 /// common for schedules
-pub type SubWorld<'a, T> = SubWorldRaw<AtomicRef<'a, World>, T>;
+pub type SubWorld<'a, T, F = ()> = SubWorldRaw<AtomicRef<'a, World>, T, F>;
 /// Type alias for a subworld referencing the world by a [std::cell::Ref]
-pub type SubWorldRefCell<'a, T> = SubWorldRaw<std::cell::Ref<'a, World>, T>;
+pub type SubWorldRefCell<'a, T, F = ()> = SubWorldRaw<std::cell::Ref<'a, World>, T, F>;
 /// Type alias for a subworld referencing the world by a reference
-pub type SubWorldRef<'a, T> = SubWorldRaw<&'a World, T>;
+pub type SubWorldRef<'a, T, F = ()> = SubWorldRaw<&'a World, T, F>;
 
 /// An empty subworld, can not access any components
 pub type EmptyWorld<'a> = SubWorldRef<'a, ()>;
@@ -26,23 +32,54 @@ pub type EmptyWorld<'a> = SubWorldRef<'a, ()>;
 ///
 /// Type alises are provided for the most common usages, with [SubWorld] being
 /// the one used by [Schedule](crate::Schedule).
-pub struct SubWorldRaw<A, T> {
+///
+/// The subworld can further be restricted to the entities passing the
+/// [`ArchetypeFilter`] `F`, see [`filter`](crate::filter). Queries are
+/// restricted to the filtered entities, and single entity accessors fail for
+/// entities not passing the filter.
+pub struct SubWorldRaw<A, T, F = ()> {
     pub(crate) world: A,
-    marker: PhantomData<T>,
+    ticks: SystemTicks,
+    marker: PhantomData<(T, F)>,
 }
 
-impl<A, T> SubWorldRaw<A, T> {
+impl<A, T, F> SubWorldRaw<A, T, F> {
     /// Splits the world into a subworld. No borrow checking is performed so may
     /// fail during query unless guarded otherwise.
     pub fn new(world: A) -> Self {
         Self {
             world,
+            ticks: SystemTicks::default(),
             marker: PhantomData,
         }
     }
+
+    /// Sets the tick which change detection filters compare against
+    pub fn with_last_run(self, last_run: Tick) -> Self {
+        self.with_ticks(SystemTicks::new(last_run))
+    }
+
+    /// Sets the ticks which change detection filters compare against
+    pub fn with_ticks(mut self, ticks: SystemTicks) -> Self {
+        self.ticks = ticks;
+        self
+    }
+
+    /// Returns the tick at which the system owning the subworld last ran.
+    ///
+    /// Subworlds created outside a schedule consider every value as changed.
+    pub fn last_run(&self) -> Tick {
+        self.ticks.last_run
+    }
+
+    /// Returns the ticks of the previous execution of the system owning the
+    /// subworld
+    pub fn ticks(&self) -> SystemTicks {
+        self.ticks
+    }
 }
 
-impl<A, T: ComponentBorrow> SubWorldRaw<A, T> {
+impl<A, T: ComponentBorrow, F> SubWorldRaw<A, T, F> {
     /// Returns true if the subworld can access the borrow of T
     pub fn has<U: IntoAccess>(&self) -> bool {
         T::has::<U>()
@@ -54,18 +91,62 @@ impl<A, T: ComponentBorrow> SubWorldRaw<A, T> {
     }
 }
 
-impl<'w, A: 'w + Deref<Target = World>, T: ComponentBorrow> SubWorldRaw<A, T> {
+impl<'w, A: 'w + Deref<Target = World>, T: ComponentBorrow, F: ArchetypeFilter>
+    SubWorldRaw<A, T, F>
+{
     /// Query the subworld.
     /// # Panics
     /// Panics if the query items are not a compatible subset of the subworld.
-    pub fn query<Q: Query + Subset>(&self) -> QueryBorrow<'_, Q> {
+    pub fn query<Q: Query + Subset>(&self) -> QueryBorrow<'_, F::Query<Q>> {
         self.try_query()
             .expect("Failed to execute query on subworld")
     }
 
+    /// Query the subworld, failing if the query items are not a compatible
+    /// subset of the subworld.
+    pub fn try_query<Q: Query + Subset>(&self) -> Result<QueryBorrow<'_, F::Query<Q>>> {
+        if !self.has_all::<Q>() {
+            return Err(Error::IncompatibleSubworld {
+                subworld: type_name::<T>(),
+                query: type_name::<Q>(),
+            });
+        }
+
+        Ok(self.world.query())
+    }
+
+    /// Query the subworld for the entities passing the change detection filter
+    /// `F`, such as [`Changed<T>`](crate::Changed), since the system last ran.
+    ///
+    /// The filter is evaluated per entity, and borrows the tracked components
+    /// immutably. A tracked component can thus not be both filtered and
+    /// mutably queried.
+    /// # Panics
+    /// Panics if the query or filter items are not a compatible subset of the
+    /// subworld.
+    pub fn query_filtered<Q: Query + Subset, C: Filter>(&self) -> FilteredQuery<'_, F::Query<Q>, C>
+    where
+        C::Query: Subset,
+    {
+        if !self.has_all::<Q>() || !self.has_all::<C::Query>() {
+            panic!(
+                "Failed to execute query on subworld: {}",
+                Error::IncompatibleSubworld {
+                    subworld: type_name::<T>(),
+                    query: type_name::<(Q, C)>(),
+                }
+            );
+        }
+
+        FilteredQuery::new(self.world.query(), self.ticks)
+    }
+
     /// Query the subworld for a single entity.
     /// Wraps the hecs::NoSuchEntity error and provides the entity id
-    pub fn query_one<Q: Query + Subset>(&'w self, entity: Entity) -> Result<QueryOne<'w, Q>> {
+    pub fn query_one<Q: Query + Subset>(
+        &'w self,
+        entity: Entity,
+    ) -> Result<QueryOne<'w, F::Query<Q>>> {
         if !self.has_all::<Q>() {
             return Err(Error::IncompatibleSubworld {
                 subworld: type_name::<T>(),
@@ -84,7 +165,7 @@ impl<'w, A: 'w + Deref<Target = World>, T: ComponentBorrow> SubWorldRaw<A, T> {
     /// Get a single component from the world.
     ///
     /// Wraps the hecs::NoSuchEntity error and provides the entity id
-    pub fn get<C: Component>(&self, entity: Entity) -> Result<hecs::Ref<C>> {
+    pub fn get<C: Component>(&self, entity: Entity) -> Result<hecs::Ref<'_, C>> {
         if !self.has::<&C>() {
             return Err(Error::IncompatibleSubworld {
                 subworld: type_name::<T>(),
@@ -92,6 +173,8 @@ impl<'w, A: 'w + Deref<Target = World>, T: ComponentBorrow> SubWorldRaw<A, T> {
             });
         }
 
+        self.check_filter(entity)?;
+
         match self.world.get::<&C>(entity) {
             Ok(val) => Ok(val),
             Err(hecs::ComponentError::NoSuchEntity) => Err(Error::NoSuchEntity(entity)),
@@ -101,17 +184,74 @@ impl<'w, A: 'w + Deref<Target = World>, T: ComponentBorrow> SubWorldRaw<A, T> {
         }
     }
 
-    /// Get a single component from the world.
+    /// Get a single component mutably from the world. Requires exclusive
+    /// access to the component.
     ///
     /// Wraps the hecs::NoSuchEntity error and provides the entity id
-    pub fn get_mut<C: Component>(&self, entity: Entity) -> Result<hecs::RefMut<C>> {
-        if !self.has::<&C>() {
+    pub fn get_mut<C: Component>(&self, entity: Entity) -> Result<hecs::RefMut<'_, C>> {
+        if !self.has::<&mut C>() {
             return Err(Error::IncompatibleSubworld {
                 subworld: type_name::<T>(),
-                query: type_name::<&C>(),
+                query: type_name::<&mut C>(),
             });
         }
 
+        self.check_filter(entity)?;
+
+        match self.world.get::<&mut C>(entity) {
+            Ok(val) => Ok(val),
+            Err(hecs::ComponentError::NoSuchEntity) => Err(Error::NoSuchEntity(entity)),
+            Err(hecs::ComponentError::MissingComponent(name)) => {
+                Err(Error::MissingComponent(entity, name))
+            }
+        }
+    }
+
+    /// Query the subworld, proving at compile time that the query is a subset
+    /// of the subworld. The second argument is inferred, see [`SubsetOf`].
+    pub fn query_static<Q: Query + SubsetOf<T, I>, I>(&self) -> QueryBorrow<'_, F::Query<Q>> {
+        self.world.query()
+    }
+
+    /// Query the subworld for a single entity, proving at compile time that
+    /// the query is a subset of the subworld.
+    pub fn query_one_static<Q: Query + SubsetOf<T, I>, I>(
+        &'w self,
+        entity: Entity,
+    ) -> Result<QueryOne<'w, F::Query<Q>>> {
+        let query = self
+            .world
+            .query_one(entity)
+            .map_err(|_| Error::NoSuchEntity(entity))?;
+
+        Ok(QueryOne::new(entity, query))
+    }
+
+    /// Get a single component from the world, proving at compile time that
+    /// the subworld can read it.
+    pub fn get_static<C: Component, I>(&self, entity: Entity) -> Result<hecs::Ref<'_, C>>
+    where
+        T: HasRead<C, I>,
+    {
+        self.check_filter(entity)?;
+
+        match self.world.get::<&C>(entity) {
+            Ok(val) => Ok(val),
+            Err(hecs::ComponentError::NoSuchEntity) => Err(Error::NoSuchEntity(entity)),
+            Err(hecs::ComponentError::MissingComponent(name)) => {
+                Err(Error::MissingComponent(entity, name))
+            }
+        }
+    }
+
+    /// Get a single component mutably from the world, proving at compile time
+    /// that the subworld can write it.
+    pub fn get_mut_static<C: Component, I>(&self, entity: Entity) -> Result<hecs::RefMut<'_, C>>
+    where
+        T: HasWrite<C, I>,
+    {
+        self.check_filter(entity)?;
+
         match self.world.get::<&mut C>(entity) {
             Ok(val) => Ok(val),
             Err(hecs::ComponentError::NoSuchEntity) => Err(Error::NoSuchEntity(entity)),
@@ -126,11 +266,30 @@ impl<'w, A: 'w + Deref<Target = World>, T: ComponentBorrow> SubWorldRaw<A, T> {
         self.world.reserve_entities(count)
     }
 
-    /// Query the subworld.
+    #[cfg(feature = "parallel")]
+    /// Query the subworld in parallel, see [`ParQuery`](crate::ParQuery).
     /// # Panics
     /// Panics if the query items are not a compatible subset of the subworld.
-    pub fn query_par<Q: Query + Subset>(&self) -> QueryBorrow<'_, Q> {
-        self.try_query()
-            .expect("Failed to execute query on subworld")
+    pub fn query_par<Q: Query + Subset>(&self) -> crate::ParQuery<'_, F::Query<Q>> {
+        crate::ParQuery::new(self.query())
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    #[deprecated(note = "parallel queries require the `parallel` feature, use `query` instead")]
+    /// Query the subworld. Without the `parallel` feature, this is the same as
+    /// [`Self::query`].
+    /// # Panics
+    /// Panics if the query items are not a compatible subset of the subworld.
+    pub fn query_par<Q: Query + Subset>(&self) -> QueryBorrow<'_, F::Query<Q>> {
+        self.query()
+    }
+
+    /// Fails if `entity` does not pass the filter of the subworld
+    fn check_filter(&self, entity: Entity) -> Result<()> {
+        match self.world.satisfies::<F::Query<()>>(entity) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(Error::UnsatisfiedQuery(entity, type_name::<F>())),
+            Err(_) => Err(Error::NoSuchEntity(entity)),
+        }
     }
 }
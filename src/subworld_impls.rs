@@ -5,31 +5,61 @@ use hecs::{Component, Entity, Query, QueryBorrow, World};
 
 use crate::{
     borrow::{Borrows, ComponentBorrow, ContextBorrow},
+    filter::ArchetypeFilter,
     traits::View,
     Access, Context, EmptyWorld, Error, IntoAccess, QueryOne, Result, SubWorld, SubWorldRaw,
-    SubWorldRef, Subset,
+    SubWorldRef, Subset, SubsetOf,
 };
 
-impl<A: Deref<Target = World>, T: Query> SubWorldRaw<A, T> {
+impl<A: Deref<Target = World>, T: Query, F: ArchetypeFilter> SubWorldRaw<A, T, F> {
     /// Query the full access of the subworld. Does not fail as access is
     /// guaranteed
-    pub fn native_query(&self) -> QueryBorrow<'_, T> {
-        self.world.query::<T>()
+    pub fn native_query(&self) -> QueryBorrow<'_, F::Query<T>> {
+        self.world.query()
     }
 }
 
-impl<A: ExternalClone, T: ComponentBorrow> SubWorldRaw<A, T> {
-    /// Splits the subworld further into a compatible subworld. Fails if not
-    /// compatible
-    pub fn split<U: ComponentBorrow + Subset>(&self) -> Result<SubWorldRaw<A, U>> {
+impl<A: ExternalClone, T: ComponentBorrow, F> SubWorldRaw<A, T, F> {
+    /// Splits the subworld further into a compatible subworld, which keeps
+    /// the filter of the subworld. Fails if not compatible
+    pub fn split<U: ComponentBorrow + Subset>(&self) -> Result<SubWorldRaw<A, U, F>> {
         if !self.has_all::<U>() {
             return Err(Error::IncompatibleSubworld {
                 subworld: type_name::<T>(),
-                query: type_name::<SubWorldRaw<A, U>>(),
+                query: type_name::<SubWorldRaw<A, U, F>>(),
             });
         }
 
-        Ok(SubWorldRaw::new(A::external_clone(&self.world)))
+        Ok(SubWorldRaw::new(A::external_clone(&self.world)).with_ticks(self.ticks()))
+    }
+
+    /// Splits the subworld into two compatible subworlds whose accesses do
+    /// not conflict, such that the halves can be used concurrently, such as
+    /// from different threads.
+    ///
+    /// Fails if `U` or `V` is not compatible, or if both access the same
+    /// component and one of them exclusively.
+    #[allow(clippy::type_complexity)]
+    pub fn split_disjoint<U, V>(&self) -> Result<(SubWorldRaw<A, U, F>, SubWorldRaw<A, V, F>)>
+    where
+        U: ComponentBorrow + Subset,
+        V: ComponentBorrow + Subset,
+    {
+        let conflicts = U::borrows()
+            .iter()
+            .any(|a| V::borrows().iter().any(|b| a.conflicts(b)));
+
+        if conflicts {
+            return Err(Error::ConflictingSplit(type_name::<U>(), type_name::<V>()));
+        }
+
+        Ok((self.split()?, self.split()?))
+    }
+
+    /// Splits the subworld further, proving at compile time that `U` is a
+    /// subset of the subworld
+    pub fn split_static<U: ComponentBorrow + SubsetOf<T, I>, I>(&self) -> SubWorldRaw<A, U, F> {
+        SubWorldRaw::new(A::external_clone(&self.world)).with_ticks(self.ticks())
     }
 }
 
@@ -57,7 +87,7 @@ impl<T> ExternalClone for AtomicRef<'_, T> {
     }
 }
 
-impl<'a, A, T> View<'a> for SubWorldRaw<A, T>
+impl<'a, A, T, F> View<'a> for SubWorldRaw<A, T, F>
 where
     A: Deref<Target = World>,
     T: ComponentBorrow,
@@ -69,7 +99,7 @@ where
     }
 }
 
-impl<'a, T> ContextBorrow<'a> for SubWorld<'a, T> {
+impl<'a, T, F> ContextBorrow<'a> for SubWorld<'a, T, F> {
     type Target = Self;
 
     fn borrow(context: &'a Context) -> Result<Self> {
@@ -79,25 +109,25 @@ impl<'a, T> ContextBorrow<'a> for SubWorld<'a, T> {
             .map_err(|_| Error::Borrow(type_name::<T>()))
             .map(|cell| AtomicRef::map(cell, |val| unsafe { val.cast().as_ref() }))?;
 
-        Ok(Self::new(val))
+        Ok(Self::new(val).with_ticks(context.ticks()))
     }
 }
 
-impl<A: ExternalClone, T: ComponentBorrow, U: ComponentBorrow + Subset> From<&SubWorldRaw<A, T>>
-    for SubWorldRaw<A, U>
+impl<A: ExternalClone, T: ComponentBorrow, U: ComponentBorrow + Subset, F>
+    From<&SubWorldRaw<A, T, F>> for SubWorldRaw<A, U, F>
 {
-    fn from(value: &SubWorldRaw<A, T>) -> Self {
+    fn from(value: &SubWorldRaw<A, T, F>) -> Self {
         value.split().expect("Incompatible subworld")
     }
 }
 
-impl<A, T> From<A> for SubWorldRaw<A, T> {
+impl<A, T, F> From<A> for SubWorldRaw<A, T, F> {
     fn from(world: A) -> Self {
         Self::new(world)
     }
 }
 
-impl<'a, T> From<&'a Context<'a>> for SubWorldRaw<AtomicRef<'a, World>, T> {
+impl<'a, T, F> From<&'a Context<'a>> for SubWorldRaw<AtomicRef<'a, World>, T, F> {
     fn from(context: &'a Context) -> Self {
         let borrow = context
             .cell::<&World>()
@@ -106,7 +136,7 @@ impl<'a, T> From<&'a Context<'a>> for SubWorldRaw<AtomicRef<'a, World>, T> {
 
         let val = AtomicRef::map(borrow, |val| unsafe { val.cast().as_ref() });
 
-        Self::new(val)
+        Self::new(val).with_ticks(context.ticks())
     }
 }
 
@@ -118,9 +148,12 @@ impl<'a, 'b, T: ComponentBorrow, U: ComponentBorrow + Subset> From<&'b SubWorld<
     }
 }
 
-impl<A, T: ComponentBorrow + Query> ComponentBorrow for SubWorldRaw<A, T> {
+impl<A, T: ComponentBorrow + Query, F: ArchetypeFilter> ComponentBorrow for SubWorldRaw<A, T, F> {
     fn borrows() -> Borrows {
-        let mut access = T::borrows();
+        let mut access: Borrows = T::borrows()
+            .into_iter()
+            .map(|access| access.filtered::<F>())
+            .collect();
         access.push(Access::of::<&World>());
         access
     }
@@ -138,57 +171,69 @@ impl<A, T: ComponentBorrow + Query> ComponentBorrow for SubWorldRaw<A, T> {
 pub trait GenericWorld {
     /// Transform this into a subworld which borrows no components.
     /// This is useful for concurrent access of entities.
-    fn to_empty(&self) -> EmptyWorld {
+    fn to_empty(&self) -> EmptyWorld<'_> {
         self.to_ref()
     }
 
     /// Convert the subworld into another holding an internal reference to the original world.
-    fn to_ref<T: ComponentBorrow + Subset>(&self) -> SubWorldRef<T>;
+    ///
+    /// # Panics
+    /// Panics if `T` is not a subset of the subworld. See [`Self::try_to_ref`].
+    fn to_ref<T: ComponentBorrow + Subset>(&self) -> SubWorldRef<'_, T>;
+
+    /// Convert the subworld into another holding an internal reference to the
+    /// original world. Fails if `T` is not a subset of the subworld.
+    ///
+    /// Defaults to [`Self::to_ref`], which should be overridden by worlds
+    /// which may not provide `T`.
+    fn try_to_ref<T: ComponentBorrow + Subset>(&self) -> Result<SubWorldRef<'_, T>> {
+        Ok(self.to_ref())
+    }
+
     /// Queries the world
-    fn try_query<Q: Query + Subset>(&self) -> Result<QueryBorrow<Q>>;
+    fn try_query<Q: Query + Subset>(&self) -> Result<QueryBorrow<'_, Q>>;
     /// Queries the world for a specific entity
-    fn try_query_one<Q: Query + Subset>(&self, entity: Entity) -> Result<QueryOne<Q>>;
+    fn try_query_one<Q: Query + Subset>(&self, entity: Entity) -> Result<QueryOne<'_, Q>>;
 
     /// Get a single component for an entity
     /// Returns the contextual result since hecs-schedule is required to be imported
     /// anyway
-    fn try_get<C: Component>(&self, entity: Entity) -> Result<hecs::Ref<C>>;
+    fn try_get<C: Component>(&self, entity: Entity) -> Result<hecs::Ref<'_, C>>;
 
-    /// Get a single component for an entity
+    /// Get a single component mutably for an entity. Requires exclusive
+    /// access to the component.
     /// Returns the contextual result since hecs-schedule is required to be imported
     /// anyway
-    fn try_get_mut<C: Component>(&self, entity: Entity) -> Result<hecs::RefMut<C>>;
+    fn try_get_mut<C: Component>(&self, entity: Entity) -> Result<hecs::RefMut<'_, C>>;
 
     /// Reserve an entity
     fn reserve(&self) -> Entity;
 }
 
+// Filtered subworlds are excluded, as `to_ref` would escape the filter
 impl<A: Deref<Target = World>, T: ComponentBorrow> GenericWorld for SubWorldRaw<A, T> {
-    fn to_ref<U: ComponentBorrow + Subset>(&self) -> SubWorldRef<U> {
+    fn to_ref<U: ComponentBorrow + Subset>(&self) -> SubWorldRef<'_, U> {
+        self.try_to_ref().unwrap()
+    }
+
+    fn try_to_ref<U: ComponentBorrow + Subset>(&self) -> Result<SubWorldRef<'_, U>> {
         let world = self.world.deref();
-        SubWorldRef::<T>::new(world).split().unwrap()
+        SubWorldRef::<T>::new(world).split()
     }
 
     fn try_query<Q: Query + Subset>(&self) -> Result<QueryBorrow<'_, Q>> {
-        if !self.has_all::<Q>() {
-            return Err(Error::IncompatibleSubworld {
-                subworld: type_name::<T>(),
-                query: type_name::<Q>(),
-            });
-        } else {
-            Ok(self.world.query())
-        }
+        SubWorldRaw::try_query(self)
     }
 
     fn try_query_one<Q: Query + Subset>(&self, entity: Entity) -> Result<QueryOne<'_, Q>> {
         self.query_one(entity)
     }
 
-    fn try_get<C: Component>(&self, entity: Entity) -> Result<hecs::Ref<C>> {
+    fn try_get<C: Component>(&self, entity: Entity) -> Result<hecs::Ref<'_, C>> {
         self.get(entity)
     }
 
-    fn try_get_mut<C: Component>(&self, entity: Entity) -> Result<hecs::RefMut<C>> {
+    fn try_get_mut<C: Component>(&self, entity: Entity) -> Result<hecs::RefMut<'_, C>> {
         self.get_mut(entity)
     }
 
@@ -199,22 +244,22 @@ impl<A: Deref<Target = World>, T: ComponentBorrow> GenericWorld for SubWorldRaw<
 }
 
 impl GenericWorld for World {
-    fn to_ref<T: ComponentBorrow + Subset>(&self) -> SubWorldRef<T> {
+    fn to_ref<T: ComponentBorrow + Subset>(&self) -> SubWorldRef<'_, T> {
         SubWorldRef::new(self)
     }
 
-    fn try_query<Q: Query + Subset>(&self) -> Result<QueryBorrow<Q>> {
+    fn try_query<Q: Query + Subset>(&self) -> Result<QueryBorrow<'_, Q>> {
         Ok(self.query())
     }
 
-    fn try_query_one<Q: Query + Subset>(&self, entity: Entity) -> Result<QueryOne<Q>> {
+    fn try_query_one<Q: Query + Subset>(&self, entity: Entity) -> Result<QueryOne<'_, Q>> {
         match self.query_one(entity) {
             Ok(val) => Ok(QueryOne::new(entity, val)),
             Err(_) => Err(Error::NoSuchEntity(entity)),
         }
     }
 
-    fn try_get<C: Component>(&self, entity: Entity) -> Result<hecs::Ref<C>> {
+    fn try_get<C: Component>(&self, entity: Entity) -> Result<hecs::Ref<'_, C>> {
         match self.get::<&C>(entity) {
             Ok(val) => Ok(val),
             Err(hecs::ComponentError::NoSuchEntity) => Err(Error::NoSuchEntity(entity)),
@@ -224,7 +269,7 @@ impl GenericWorld for World {
         }
     }
 
-    fn try_get_mut<C: Component>(&self, entity: Entity) -> Result<hecs::RefMut<C>> {
+    fn try_get_mut<C: Component>(&self, entity: Entity) -> Result<hecs::RefMut<'_, C>> {
         match self.get::<&mut C>(entity) {
             Ok(val) => Ok(val),
             Err(hecs::ComponentError::NoSuchEntity) => Err(Error::NoSuchEntity(entity)),
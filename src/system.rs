@@ -1,10 +1,17 @@
 //! Provides system which are an abstraction for anything that can be executed
 //! against a [Context](crate::Context).
-use std::{any::type_name, borrow::Cow};
+use std::{
+    any::{type_name, Any, TypeId},
+    borrow::Cow,
+    collections::{hash_map::Entry, HashMap},
+    sync::{atomic::AtomicU64, Arc},
+};
+
+use atomic_refcell::{AtomicRefCell, AtomicRefMut};
 
 use crate::{
     borrow::{Borrows, ComponentBorrow, ContextBorrow, IntoBorrow},
-    Context, Result,
+    Context, Error, ResourceTicks, Result, SystemTicks, Tick,
 };
 
 /// System name alias
@@ -15,12 +22,23 @@ pub type SystemName = Cow<'static, str>;
 pub trait System<Args, Ret> {
     /// Executes the by borrowing from context
     fn execute(&mut self, context: &Context) -> Result<()>;
+
+    /// Executes the system like [`Self::execute`], and returns whether it
+    /// actually ran. Systems which were skipped, such as by
+    /// [`System::run_if`], do not advance their change ticks.
+    fn run(&mut self, context: &Context) -> Result<bool> {
+        self.execute(context).map(|_| true)
+    }
+
     /// Returns the system name. Used for debug purposes
     fn name(&self) -> SystemName;
 
     /// Returns which data will be accessed
     fn borrows() -> Borrows;
 
+    /// Initializes the state kept between executions of the system
+    fn init_state(_state: &mut SystemState) {}
+
     /// Wrap the system with a custom name
     fn named<S: Into<Cow<'static, str>>>(self, name: S) -> NamedSystem<Self>
     where
@@ -31,6 +49,17 @@ pub trait System<Args, Ret> {
             name: name.into(),
         }
     }
+
+    /// Wrap the system to only run when `condition` holds
+    fn run_if<C: Condition>(self, condition: C) -> RunIf<Self, C>
+    where
+        Self: Sized,
+    {
+        RunIf {
+            inner: self,
+            condition,
+        }
+    }
 }
 
 macro_rules! tuple_impl {
@@ -58,6 +87,10 @@ macro_rules! tuple_impl {
                     $(.chain($name::borrows().iter())) *).cloned()
                 .collect()
             }
+
+            fn init_state(state: &mut SystemState) {
+                $($name::init_state(state);)*
+            }
         }
 
         impl<Err, Func, $($name,) *> System<($($name,)*), std::result::Result<(), Err>> for Func
@@ -86,6 +119,10 @@ macro_rules! tuple_impl {
                     $(.chain($name::borrows().iter())) *).cloned()
                 .collect()
             }
+
+            fn init_state(state: &mut SystemState) {
+                $($name::init_state(state);)*
+            }
         }
     };
 }
@@ -132,6 +169,10 @@ impl<F: System<Args, Ret>, Args, Ret> System<Args, Ret> for NamedSystem<F> {
         self.inner.execute(context)
     }
 
+    fn run(&mut self, context: &Context) -> Result<bool> {
+        self.inner.run(context)
+    }
+
     fn name(&self) -> SystemName {
         self.name.clone()
     }
@@ -139,13 +180,168 @@ impl<F: System<Args, Ret>, Args, Ret> System<Args, Ret> for NamedSystem<F> {
     fn borrows() -> Borrows {
         F::borrows()
     }
+
+    fn init_state(state: &mut SystemState) {
+        F::init_state(state)
+    }
+}
+
+/// Decides whether a system wrapped by [`System::run_if`] runs, such as
+/// [`in_state`](crate::in_state).
+pub trait Condition {
+    /// Returns true if the system should run
+    fn evaluate(&mut self, context: &Context) -> Result<bool>;
+
+    /// Returns which data will be accessed by the condition
+    fn borrows() -> Borrows;
+}
+
+/// A wrapper for running a system only when a [`Condition`] holds
+pub struct RunIf<F, C> {
+    inner: F,
+    condition: C,
+}
+
+impl<F: System<Args, Ret>, C: Condition, Args, Ret> System<Args, Ret> for RunIf<F, C> {
+    fn execute(&mut self, context: &Context) -> Result<()> {
+        self.run(context).map(|_| ())
+    }
+
+    fn run(&mut self, context: &Context) -> Result<bool> {
+        if self.condition.evaluate(context)? {
+            self.inner.run(context)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn name(&self) -> SystemName {
+        self.inner.name()
+    }
+
+    fn borrows() -> Borrows {
+        F::borrows().into_iter().chain(C::borrows()).collect()
+    }
+
+    fn init_state(state: &mut SystemState) {
+        F::init_state(state)
+    }
+}
+
+type StateCell = AtomicRefCell<Box<dyn Any + Send + Sync>>;
+
+#[derive(Default)]
+/// State kept between executions of a system, such as the read position of an
+/// [`EventReader`](crate::EventReader).
+///
+/// Each value is identified by its type. The state is owned by the schedule
+/// and made available to the system's parameters through
+/// [`Context::state`].
+pub struct SystemState {
+    values: HashMap<TypeId, StateCell>,
+    ticks: SystemTicks,
+    resource_ticks: Option<Vec<(TypeId, Arc<AtomicU64>)>>,
+}
+
+impl SystemState {
+    /// Creates a new empty state
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the tick at which the system last ran, or the default tick if
+    /// it has not yet run
+    pub fn last_run(&self) -> Tick {
+        self.ticks.last_run
+    }
+
+    /// Returns the ticks of the previous execution of the system
+    pub fn ticks(&self) -> SystemTicks {
+        self.ticks
+    }
+
+    /// Sets the ticks of the previous execution of the system
+    pub fn set_ticks(&mut self, ticks: SystemTicks) {
+        self.ticks = ticks
+    }
+
+    /// Resolves the change ticks of the resources in `borrows`, unless
+    /// already resolved
+    pub(crate) fn track_resources(&mut self, borrows: &Borrows, ticks: &ResourceTicks) {
+        self.resource_ticks.get_or_insert_with(|| {
+            let mut resolved: Vec<_> = borrows
+                .iter()
+                .map(|access| (access.id(), ticks.get(access.id())))
+                .collect();
+            resolved.sort_by_key(|(id, _)| *id);
+            resolved.dedup_by_key(|(id, _)| *id);
+            resolved
+        });
+    }
+
+    /// Returns the change tick of the resource with type `ty`, if tracked
+    pub(crate) fn resource_tick(&self, ty: TypeId) -> Option<&AtomicU64> {
+        self.resource_ticks
+            .as_ref()?
+            .iter()
+            .find(|(id, _)| *id == ty)
+            .map(|(_, tick)| &**tick)
+    }
+
+    /// Inserts the default value of `S`, unless already present
+    pub fn init<S: Default + Send + Sync + 'static>(&mut self) {
+        self.values
+            .entry(TypeId::of::<S>())
+            .or_insert_with(|| AtomicRefCell::new(Box::<S>::default()));
+    }
+
+    /// Inserts the default value of `S`, which is kept for a single
+    /// parameter of the system, such as the read position of an
+    /// [`EventReader`](crate::EventReader).
+    ///
+    /// # Panics
+    /// Panics if `S` is already present, since two parameters of the system
+    /// would then share the value.
+    pub fn init_unique<S: Default + Send + Sync + 'static>(&mut self) {
+        match self.values.entry(TypeId::of::<S>()) {
+            Entry::Occupied(_) => panic!(
+                "Two parameters of the system share the state {}. Use a single parameter instead",
+                type_name::<S>()
+            ),
+            Entry::Vacant(entry) => {
+                entry.insert(AtomicRefCell::new(Box::<S>::default()));
+            }
+        }
+    }
+
+    /// Inserts `value`, replacing any previous value of `S`
+    pub fn insert<S: Send + Sync + 'static>(&mut self, value: S) {
+        self.values
+            .insert(TypeId::of::<S>(), AtomicRefCell::new(Box::new(value)));
+    }
+
+    /// Exclusively borrows the value of `S`
+    pub fn get<S: 'static>(&self) -> Result<AtomicRefMut<'_, S>> {
+        let cell = self
+            .values
+            .get(&TypeId::of::<S>())
+            .ok_or(Error::MissingState(type_name::<S>()))?;
+
+        let val = cell
+            .try_borrow_mut()
+            .map_err(|_| Error::BorrowMut(type_name::<S>()))?;
+
+        Ok(AtomicRefMut::map(val, |val| {
+            val.downcast_mut().expect("State type mismatch")
+        }))
+    }
 }
 
 impl_for_tuples!(tuple_impl);
 
 #[cfg(test)]
 mod tests {
-    use crate::{system::System, Context, GenericWorld, IntoData, Read, SubWorld};
+    use crate::{system::System, Context, IntoData, Read, SubWorld};
     use hecs::World;
 
     use anyhow::{ensure, Result};
@@ -4,6 +4,9 @@ use hecs::{Query, QueryBorrow};
 #[cfg(feature = "parallel")]
 use hecs::Entity;
 
+#[cfg(feature = "parallel")]
+use crate::{query::batches, BatchPlan, BatchSize};
+
 /// Traits for types which represent a view or subset of some other type.
 pub trait View<'a> {
     /// The type which View comes from
@@ -30,46 +33,170 @@ impl<'a, T> View<'a> for &'a mut T {
     }
 }
 
-/// Extends the queries for additional paralell operation
+/// Extends the queries for additional paralell operation.
+///
+/// The matched entities are split into batches of entities of the same
+/// archetype, sized by [`BatchSize`], which are processed in parallel. A
+/// number converts to a fixed batch size. Results
+/// are combined in the order of the batches, and are thus deterministic for a
+/// given world and batch size.
 pub trait QueryExt {
     /// Item returned by the query
     type Item<'a>;
     /// Execute a function for each item of the query in pararell using rayon.
     #[cfg(feature = "parallel")]
-    fn par_for_each<'a>(self, batch_size: u32, func: impl Fn((Entity, Self::Item<'a>)) + Send + Sync);
+    fn par_for_each<'a>(
+        self,
+        batch_size: impl Into<BatchSize>,
+        func: impl Fn((Entity, Self::Item<'a>)) + Send + Sync,
+    );
     /// Fallible version of [`QueryBorrow::par_for_each`]
     #[cfg(feature = "parallel")]
     fn try_par_for_each<'a, E: Send>(
         self,
-        batch_size: u32,
+        batch_size: impl Into<BatchSize>,
         func: impl Fn((Entity, Self::Item<'a>)) -> Result<(), E> + Send + Sync,
     ) -> Result<(), E>;
+
+    /// Maps each item of the query in parallel, and collects the results in
+    /// the order of iteration.
+    #[cfg(feature = "parallel")]
+    fn par_map_collect<'a, R: Send>(
+        self,
+        batch_size: impl Into<BatchSize>,
+        func: impl Fn((Entity, Self::Item<'a>)) -> R + Send + Sync,
+    ) -> Vec<R>;
+
+    /// Folds the items of each batch in parallel, starting from `init`.
+    /// Returns the accumulator of each batch, in the order of the batches.
+    #[cfg(feature = "parallel")]
+    fn par_fold<'a, T: Send>(
+        self,
+        batch_size: impl Into<BatchSize>,
+        init: impl Fn() -> T + Send + Sync,
+        fold: impl Fn(T, (Entity, Self::Item<'a>)) -> T + Send + Sync,
+    ) -> Vec<T>;
+
+    /// Folds the items of each batch in parallel, and reduces the
+    /// accumulators of the batches in order using `reduce`.
+    ///
+    /// Like in rayon, `init` should return an identity value, such as zero for
+    /// a sum, since each batch starts from it. Returns `init()` if no entity
+    /// matches.
+    #[cfg(feature = "parallel")]
+    fn par_reduce<'a, T: Send>(
+        self,
+        batch_size: impl Into<BatchSize>,
+        init: impl Fn() -> T + Send + Sync,
+        fold: impl Fn(T, (Entity, Self::Item<'a>)) -> T + Send + Sync,
+        reduce: impl Fn(T, T) -> T,
+    ) -> T
+    where
+        Self: Sized,
+    {
+        let mut batches = self.par_fold(batch_size, &init, fold).into_iter();
+        match batches.next() {
+            Some(first) => batches.fold(first, reduce),
+            None => init(),
+        }
+    }
+
+    /// Sums the value returned by `func` for each item in parallel
+    #[cfg(feature = "parallel")]
+    fn par_sum<'a, S: Send + std::iter::Sum>(
+        self,
+        batch_size: impl Into<BatchSize>,
+        func: impl Fn((Entity, Self::Item<'a>)) -> S + Send + Sync,
+    ) -> S;
+
+    /// Returns the plan the parallel operations follow for `batch_size` on
+    /// the query, for example to report it in profiling output. The plan only
+    /// depends on the matched archetypes and the number of threads, and is
+    /// thus the one used by a subsequent operation on the same query.
+    #[cfg(feature = "parallel")]
+    fn batch_plan(self, batch_size: impl Into<BatchSize>) -> BatchPlan;
 }
 
 impl<'w, 'q, Q> QueryExt for &'q mut QueryBorrow<'w, Q>
 where
     Q: Query,
-    for <'a> Q::Item<'a>: Send,
+    for<'a> Q::Item<'a>: Send,
 {
     type Item<'a> = Q::Item<'q>;
 
     #[cfg(feature = "parallel")]
-    fn par_for_each<'a>(self, batch_size: u32, func: impl Fn((Entity, Self::Item<'a>)) + Send + Sync) {
-        use rayon::iter::{ParallelBridge, ParallelIterator};
-        self.iter_batched(batch_size)
-            .par_bridge()
+    fn par_for_each<'a>(
+        self,
+        batch_size: impl Into<BatchSize>,
+        func: impl Fn((Entity, Self::Item<'a>)) + Send + Sync,
+    ) {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+        batches(self, batch_size.into())
+            .1
+            .into_par_iter()
             .for_each(|batch| batch.for_each(&func))
     }
 
     #[cfg(feature = "parallel")]
     fn try_par_for_each<'a, E: Send>(
         self,
-        batch_size: u32,
+        batch_size: impl Into<BatchSize>,
         func: impl Fn((Entity, Self::Item<'a>)) -> Result<(), E> + Send + Sync,
     ) -> Result<(), E> {
-        use rayon::iter::{ParallelBridge, ParallelIterator};
-        self.iter_batched(batch_size)
-            .par_bridge()
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+        batches(self, batch_size.into())
+            .1
+            .into_par_iter()
             .try_for_each(|mut batch| batch.try_for_each(&func))
     }
+
+    #[cfg(feature = "parallel")]
+    fn par_map_collect<'a, R: Send>(
+        self,
+        batch_size: impl Into<BatchSize>,
+        func: impl Fn((Entity, Self::Item<'a>)) -> R + Send + Sync,
+    ) -> Vec<R> {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+        batches(self, batch_size.into())
+            .1
+            .into_par_iter()
+            .flat_map_iter(|batch| batch.map(&func))
+            .collect()
+    }
+
+    #[cfg(feature = "parallel")]
+    fn par_fold<'a, T: Send>(
+        self,
+        batch_size: impl Into<BatchSize>,
+        init: impl Fn() -> T + Send + Sync,
+        fold: impl Fn(T, (Entity, Self::Item<'a>)) -> T + Send + Sync,
+    ) -> Vec<T> {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+        batches(self, batch_size.into())
+            .1
+            .into_par_iter()
+            .map(|batch| batch.fold(init(), &fold))
+            .collect()
+    }
+
+    #[cfg(feature = "parallel")]
+    fn par_sum<'a, S: Send + std::iter::Sum>(
+        self,
+        batch_size: impl Into<BatchSize>,
+        func: impl Fn((Entity, Self::Item<'a>)) -> S + Send + Sync,
+    ) -> S {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+        let sums: Vec<S> = batches(self, batch_size.into())
+            .1
+            .into_par_iter()
+            .map(|batch| batch.map(&func).sum())
+            .collect();
+
+        sums.into_iter().sum()
+    }
+
+    #[cfg(feature = "parallel")]
+    fn batch_plan(self, batch_size: impl Into<BatchSize>) -> BatchPlan {
+        batch_size.into().plan(self)
+    }
 }
@@ -0,0 +1,150 @@
+//! Checks every accessor of [`GenericWorld`] against the access declared by
+//! the subworld.
+use hecs::{Component, Entity, World};
+use hecs_schedule::{
+    borrow::ComponentBorrow, AllAccess, Error, GenericWorld, Schedule, SubWorld, SubWorldRef,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct A(i32);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct B(i32);
+
+/// Returns the access to `C` declared by `T` as (readable, writable)
+fn declared<T: ComponentBorrow, C: Component>() -> (bool, bool) {
+    T::borrows()
+        .iter()
+        .filter(|access| access.id() == std::any::TypeId::of::<C>())
+        .fold((false, false), |(_, write), access| {
+            (true, write || access.exclusive())
+        })
+}
+
+fn check<T: ComponentBorrow, C: Component>(world: &World, entity: Entity, all: bool) {
+    let subworld = SubWorldRef::<T>::new(world);
+    let (read, write) = if all {
+        (true, true)
+    } else {
+        declared::<T, C>()
+    };
+    let name = std::any::type_name::<(T, C)>();
+
+    assert_eq!(subworld.has::<&C>(), read, "has &C: {name}");
+    assert_eq!(subworld.has::<&mut C>(), write, "has &mut C: {name}");
+
+    assert_eq!(subworld.try_query::<&C>().is_ok(), read, "query &C: {name}");
+    assert_eq!(
+        subworld.try_query::<&mut C>().is_ok(),
+        write,
+        "query &mut C: {name}"
+    );
+    assert_eq!(
+        subworld.try_query::<Option<&C>>().is_ok(),
+        read,
+        "query Option<&C>: {name}"
+    );
+
+    assert_eq!(
+        subworld.try_query_one::<&C>(entity).is_ok(),
+        read,
+        "query_one &C: {name}"
+    );
+    assert_eq!(
+        subworld.try_query_one::<&mut C>(entity).is_ok(),
+        write,
+        "query_one &mut C: {name}"
+    );
+
+    assert_eq!(subworld.try_get::<C>(entity).is_ok(), read, "get: {name}");
+    assert_eq!(
+        subworld.try_get_mut::<C>(entity).is_ok(),
+        write,
+        "get_mut: {name}"
+    );
+
+    assert_eq!(subworld.split::<&C>().is_ok(), read, "split &C: {name}");
+    assert_eq!(
+        subworld.split::<&mut C>().is_ok(),
+        write,
+        "split &mut C: {name}"
+    );
+
+    assert_eq!(
+        subworld.try_to_ref::<&C>().is_ok(),
+        read,
+        "to_ref &C: {name}"
+    );
+    assert_eq!(
+        subworld.try_to_ref::<&mut C>().is_ok(),
+        write,
+        "to_ref &mut C: {name}"
+    );
+    if read {
+        subworld.to_ref::<&C>();
+    }
+
+    if !write {
+        assert!(matches!(
+            subworld.try_get_mut::<C>(entity),
+            Err(Error::IncompatibleSubworld { .. })
+        ));
+    }
+}
+
+macro_rules! check_all {
+    ($world: expr, $entity: expr, $($ty: ty),*) => {
+        $(
+            check::<$ty, A>($world, $entity, false);
+            check::<$ty, B>($world, $entity, false);
+        )*
+    };
+}
+
+#[test]
+fn accessors() {
+    let mut world = World::default();
+    let entity = world.spawn((A(1), B(2)));
+
+    check_all!(
+        &world,
+        entity,
+        (),
+        &A,
+        &mut A,
+        (&A,),
+        (&mut A,),
+        (&A, &B),
+        (&A, &mut B),
+        (&mut A, &B),
+        (&mut A, &mut B),
+        (&B, Option<&A>),
+        (Option<&mut A>, &B)
+    );
+
+    check::<AllAccess, A>(&world, entity, true);
+    check::<AllAccess, B>(&world, entity, true);
+
+    // The world itself has access to everything
+    assert!(world.try_get_mut::<A>(entity).is_ok());
+    assert!(world.try_get::<B>(entity).is_ok());
+}
+
+#[test]
+fn get_mut_requires_exclusive_access() {
+    let mut world = World::default();
+    let entity = world.spawn((A(1),));
+
+    let mut schedule = Schedule::builder()
+        .add_system(move |w: SubWorld<&A>| {
+            assert!(w.get::<A>(entity).is_ok());
+            assert!(w.get_mut::<A>(entity).is_err());
+        })
+        .add_system(move |w: SubWorld<&mut A>| {
+            w.get_mut::<A>(entity).unwrap().0 += 1;
+        })
+        .build();
+
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert_eq!(*world.get::<&A>(entity).unwrap(), A(2));
+}
@@ -0,0 +1,49 @@
+use hecs::World;
+use hecs_schedule::{CachedQuery, Schedule, SubWorld};
+
+#[derive(Debug, PartialEq)]
+struct Health(i32);
+
+struct Player;
+
+fn heal(mut query: CachedQuery<&mut Health>) {
+    query.query().iter().for_each(|(_, h)| h.0 += 1);
+}
+
+fn read_health(_: SubWorld<&Health>) {}
+
+#[test]
+fn cached_query() {
+    let mut world = World::default();
+    let a = world.spawn((Health(0),));
+
+    let mut schedule = Schedule::builder().add_system(heal).build();
+
+    schedule.execute_seq((&mut world,)).unwrap();
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert_eq!(*world.get::<&Health>(a).unwrap(), Health(2));
+
+    // A new archetype invalidates the matched archetypes
+    let b = world.spawn((Player, Health(0)));
+    schedule.execute_seq((&mut world,)).unwrap();
+
+    assert_eq!(*world.get::<&Health>(a).unwrap(), Health(3));
+    assert_eq!(*world.get::<&Health>(b).unwrap(), Health(1));
+}
+
+#[test]
+fn cached_query_borrows() {
+    let schedule = Schedule::builder()
+        .add_system(heal)
+        .add_system(read_health)
+        .build();
+
+    // Both systems and the final flush
+    assert_eq!(schedule.batch_info().len(), 3);
+}
+
+#[test]
+#[should_panic(expected = "share the state")]
+fn duplicate_cached_queries() {
+    Schedule::builder().add_system(|_: CachedQuery<&Health>, _: CachedQuery<&Health>| {});
+}
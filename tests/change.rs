@@ -0,0 +1,226 @@
+use std::sync::{Arc, Mutex};
+
+use hecs::{Entity, World};
+use hecs_schedule::{
+    in_state, Added, Changed, CommandBuffer, NextState, Read, Schedule, State, SubWorld, System,
+    Tracked, Write,
+};
+
+type Seen = Arc<Mutex<Vec<Entity>>>;
+
+fn record(seen: &Seen, entities: impl Iterator<Item = Entity>) {
+    let mut seen = seen.lock().unwrap();
+    seen.clear();
+    seen.extend(entities);
+    seen.sort();
+}
+
+#[test]
+fn changed() {
+    let mut world = World::default();
+
+    let a = world.spawn((Tracked::new(1_i32),));
+    let b = world.spawn((Tracked::new(2_i32),));
+
+    let before: Seen = Default::default();
+    let after: Seen = Default::default();
+    let target = Arc::new(Mutex::new(None));
+
+    let mut schedule = {
+        let (before, after, target) = (before.clone(), after.clone(), target.clone());
+        Schedule::builder()
+            .add_system(move |w: SubWorld<&Tracked<i32>>| {
+                let mut query = w.query_filtered::<(), Changed<i32>>();
+                record(&before, query.iter().map(|(e, _)| e));
+            })
+            .add_system(move |w: SubWorld<&mut Tracked<i32>>| {
+                if let Some(entity) = *target.lock().unwrap() {
+                    **w.get_mut::<Tracked<i32>>(entity).unwrap() += 1;
+                }
+            })
+            .add_system(move |w: SubWorld<&Tracked<i32>>| {
+                let mut query = w.query_filtered::<(), Changed<i32>>();
+                record(&after, query.iter().map(|(e, _)| e));
+            })
+            .build()
+    };
+
+    // Everything is new on the first execution
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert_eq!(*before.lock().unwrap(), [a, b]);
+    assert_eq!(*after.lock().unwrap(), [a, b]);
+
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert!(before.lock().unwrap().is_empty());
+    assert!(after.lock().unwrap().is_empty());
+
+    *target.lock().unwrap() = Some(b);
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert!(before.lock().unwrap().is_empty());
+    assert_eq!(*after.lock().unwrap(), [b]);
+
+    // The change is observed by the first system in the next execution
+    *target.lock().unwrap() = None;
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert_eq!(*before.lock().unwrap(), [b]);
+    assert!(after.lock().unwrap().is_empty());
+
+    // Modifications outside the schedule
+    **world.get::<&mut Tracked<i32>>(a).unwrap() = 5;
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert_eq!(*before.lock().unwrap(), [a]);
+    assert_eq!(*after.lock().unwrap(), [a]);
+}
+
+#[test]
+fn added() {
+    let mut world = World::default();
+
+    let seen: Seen = Default::default();
+    let spawned = Arc::new(Mutex::new(Vec::new()));
+
+    let mut schedule = {
+        let (seen, spawned) = (seen.clone(), spawned.clone());
+        Schedule::builder()
+            .add_system(move |w: SubWorld<(&Tracked<&'static str>, &i32)>| {
+                let mut query = w.query_filtered::<&i32, Added<&'static str>>();
+                record(&seen, query.iter().map(|(e, _)| e));
+            })
+            .add_system(move |w: SubWorld<()>, mut cmd: Write<CommandBuffer>| {
+                let entity = cmd.spawn_reserved(&w, (Tracked::new("new"), 0_i32));
+                spawned.lock().unwrap().push(entity);
+            })
+            .build()
+    };
+
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert!(seen.lock().unwrap().is_empty());
+
+    // Entities inserted at the flush are observed in the next execution
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert_eq!(*seen.lock().unwrap(), spawned.lock().unwrap()[..1]);
+
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert_eq!(*seen.lock().unwrap(), spawned.lock().unwrap()[1..2]);
+}
+
+#[test]
+fn added_before_matching() {
+    struct Marker;
+
+    let mut world = World::default();
+    let entity = world.spawn((Tracked::new(0_i32),));
+
+    let added: Seen = Default::default();
+    let changed: Seen = Default::default();
+
+    let mut schedule = {
+        let (added, changed) = (added.clone(), changed.clone());
+        Schedule::builder()
+            .add_system(move |w: SubWorld<(&Tracked<i32>, &Marker)>| {
+                let mut query = w.query_filtered::<&Marker, Added<i32>>();
+                record(&added, query.iter().map(|(e, _)| e));
+                let mut query = w.query_filtered::<&Marker, Changed<i32>>();
+                record(&changed, query.iter().map(|(e, _)| e));
+            })
+            .build()
+    };
+
+    schedule.execute_seq((&mut world,)).unwrap();
+    schedule.execute_seq((&mut world,)).unwrap();
+
+    // The value was added before the system last ran, even though it is first
+    // matched now
+    world.insert_one(entity, Marker).unwrap();
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert!(added.lock().unwrap().is_empty());
+    assert!(changed.lock().unwrap().is_empty());
+}
+
+#[test]
+fn skipped() {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Gate {
+        Open,
+        Closed,
+    }
+
+    let mut world = World::default();
+    let a = world.spawn((Tracked::new(1_i32),));
+    let b = world.spawn((Tracked::new(2_i32),));
+
+    let seen: Seen = Default::default();
+
+    let mut schedule = {
+        let seen = seen.clone();
+        Schedule::builder()
+            .add_state::<Gate>()
+            .add_system(
+                (move |w: SubWorld<&Tracked<i32>>| {
+                    let mut query = w.query_filtered::<(), Changed<i32>>();
+                    record(&seen, query.iter().map(|(e, _)| e));
+                })
+                .run_if(in_state(Gate::Open)),
+            )
+            .build()
+    };
+
+    let mut state = State::new(Gate::Open);
+    let mut next = NextState::new();
+
+    schedule
+        .execute_seq((&mut world, &mut state, &mut next))
+        .unwrap();
+    assert_eq!(*seen.lock().unwrap(), [a, b]);
+
+    next.set(Gate::Closed);
+    schedule
+        .execute_seq((&mut world, &mut state, &mut next))
+        .unwrap();
+
+    // Changed while the system is skipped
+    **world.get::<&mut Tracked<i32>>(a).unwrap() = 5;
+    schedule
+        .execute_seq((&mut world, &mut state, &mut next))
+        .unwrap();
+
+    next.set(Gate::Open);
+    schedule
+        .execute_seq((&mut world, &mut state, &mut next))
+        .unwrap();
+    assert_eq!(*seen.lock().unwrap(), [a]);
+}
+
+struct Config(u32);
+
+#[test]
+fn resource_changed() {
+    let changed = Arc::new(Mutex::new(Vec::new()));
+
+    let mut schedule = {
+        let changed = changed.clone();
+        Schedule::builder()
+            .add_system(move |config: Read<Config>| {
+                changed.lock().unwrap().push(config.is_changed())
+            })
+            .add_system(|mut config: Write<Config>, modify: Read<bool>| {
+                // Reading does not mark the resource as changed
+                if *modify && config.0 < 10 {
+                    config.0 += 1;
+                }
+            })
+            .build()
+    };
+
+    let mut config = Config(0);
+
+    // Resources are changed when first seen
+    schedule.execute_seq((&mut config, &mut false)).unwrap();
+    schedule.execute_seq((&mut config, &mut false)).unwrap();
+    schedule.execute_seq((&mut config, &mut true)).unwrap();
+    schedule.execute_seq((&mut config, &mut false)).unwrap();
+    schedule.execute_seq((&mut config, &mut false)).unwrap();
+
+    assert_eq!(*changed.lock().unwrap(), [true, false, false, true, false]);
+    assert_eq!(config.0, 1);
+}
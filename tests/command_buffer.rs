@@ -1,6 +1,9 @@
 use anyhow::ensure;
 use hecs::World;
-use hecs_schedule::{CommandBuffer, GenericWorld, Schedule, SubWorld, Write};
+use hecs_schedule::{
+    CloneRegistry, CommandBuffer, CommandKind, CommandStats, FlushPolicy, GenericWorld, Schedule,
+    SubWorld, Write,
+};
 
 #[test]
 fn test_schedule() {
@@ -56,3 +59,226 @@ fn test_schedule() {
 
     assert_eq!(value, Foo { val: 56 });
 }
+
+#[test]
+fn spawn_reserved() {
+    let mut world = World::default();
+
+    #[derive(Debug, PartialEq)]
+    struct Parent(hecs::Entity);
+
+    let mut schedule = Schedule::builder()
+        .add_system(|w: SubWorld<()>, mut cmd: Write<CommandBuffer>| {
+            let parent = cmd.spawn_reserved(&w, ("parent",));
+            cmd.spawn(("child", Parent(parent)));
+        })
+        .build();
+
+    schedule.execute_seq((&mut world,)).unwrap();
+
+    let mut query = world.query::<(&&str, &Parent)>();
+    let (_, (name, parent)) = query.iter().next().unwrap();
+    assert_eq!(*name, "child");
+    assert_eq!(*world.get::<&&str>(parent.0).unwrap(), "parent");
+}
+
+#[test]
+fn try_execute() {
+    let mut world = World::default();
+    let a = world.spawn((1_i32,));
+    let b = world.spawn((2_i32,));
+    world.despawn(b).unwrap();
+
+    let mut cmd = CommandBuffer::new();
+    cmd.set_system("remover");
+    cmd.remove_one::<f32>(a);
+    cmd.clear_system();
+    cmd.despawn(b);
+    cmd.insert_one(a, 5.0_f32);
+
+    let errors = cmd.try_execute(&mut world).unwrap_err();
+
+    assert_eq!(
+        errors
+            .iter()
+            .map(|e| (e.entity, e.kind, e.system.as_deref()))
+            .collect::<Vec<_>>(),
+        [
+            (a, CommandKind::Remove, Some("remover")),
+            (b, CommandKind::Despawn, None)
+        ]
+    );
+
+    // Commands after the failures are still applied
+    assert_eq!(*world.get::<&f32>(a).unwrap(), 5.0);
+}
+
+#[test]
+fn flush_policy() {
+    let mut world = World::default();
+    let a = world.spawn((1_i32,));
+    world.despawn(a).unwrap();
+
+    let despawn_system = move |mut cmd: Write<CommandBuffer>| cmd.despawn(a);
+
+    let mut schedule = Schedule::builder()
+        .flush_policy(FlushPolicy::Ignore)
+        .add_system(despawn_system)
+        .build();
+
+    schedule.execute_seq((&mut world,)).unwrap();
+
+    // Failures are ignored by default
+    let mut schedule = Schedule::builder().add_system(despawn_system).build();
+
+    schedule.execute_seq((&mut world,)).unwrap();
+
+    let mut schedule = Schedule::builder()
+        .flush_policy(FlushPolicy::Fail)
+        .add_system(despawn_system)
+        .build();
+
+    assert!(schedule.execute_seq((&mut world,)).is_err());
+}
+
+#[test]
+fn batch() {
+    let mut world = World::default();
+    let missing = world.spawn(());
+    world.despawn(missing).unwrap();
+
+    let mut cmd = CommandBuffer::new();
+    cmd.spawn_batch((0..1000).map(|i| (i, i as f32)));
+    cmd.execute(&mut world);
+
+    assert_eq!(world.query::<(&i32, &f32)>().iter().count(), 1000);
+
+    let entities: Vec<_> = world.iter().map(|e| e.entity()).collect();
+    cmd.insert_batch(entities.iter().map(|&e| (e, ("particle",))));
+    cmd.insert_batch([(missing, ("missing",))]);
+
+    let errors = cmd.try_execute(&mut world).unwrap_err();
+    assert!(errors
+        .iter()
+        .map(|e| (e.entity, e.kind))
+        .eq([(missing, CommandKind::Insert)]));
+
+    assert_eq!(world.query::<&&str>().iter().count(), 1000);
+}
+
+#[test]
+fn stats() {
+    let mut world = World::default();
+    let a = world.spawn((1_i32,));
+    let b = world.spawn((2_i32,));
+
+    let mut cmd = CommandBuffer::new();
+    assert!(cmd.is_empty());
+
+    cmd.spawn((5_i32,));
+    cmd.spawn_batch((0..10).map(|i| (i,)));
+    cmd.insert_batch([(a, (1.0_f32,)), (b, (2.0_f32,))]);
+    cmd.remove_one::<i32>(b);
+    cmd.despawn(a);
+    cmd.write(|_| {});
+
+    let stats = cmd.stats();
+    assert_eq!(
+        stats,
+        CommandStats {
+            spawns: 11,
+            inserts: 2,
+            removes: 1,
+            despawns: 1,
+            writes: 1,
+            triggers: 0,
+            memory: stats.memory,
+        }
+    );
+    assert!(stats.memory >= 11 * std::mem::size_of::<i32>());
+
+    assert_eq!(cmd.len(), 6);
+    assert!(cmd.entities().eq([a, b, b, a]));
+
+    cmd.execute(&mut world);
+    assert_eq!(cmd.stats().spawns, 0);
+}
+
+#[test]
+fn undo_redo() {
+    #[derive(Debug, Clone, PartialEq)]
+    struct Position(i32);
+    #[derive(Debug, Clone, PartialEq)]
+    struct Name(&'static str);
+
+    let mut registry = CloneRegistry::new();
+    registry.register::<Position>().register::<Name>();
+
+    let mut world = World::default();
+    let a = world.spawn((Position(1), Name("a")));
+    let b = world.spawn((Position(2),));
+
+    let snapshot = |world: &World| {
+        let mut entities = world
+            .iter()
+            .map(|e| {
+                (
+                    e.entity(),
+                    e.get::<&Position>().map(|v| v.0),
+                    e.get::<&Name>().map(|v| v.0),
+                )
+            })
+            .collect::<Vec<_>>();
+        entities.sort_by_key(|v| v.0);
+        entities
+    };
+
+    let before = snapshot(&world);
+
+    let mut cmd = CommandBuffer::new();
+    let c = cmd.spawn_reserved(&world, (Position(3),));
+    cmd.spawn_batch([(Position(4),), (Position(5),)]);
+    cmd.insert(a, (Position(10), Name("b")));
+    cmd.insert_one(b, Name("b"));
+    cmd.remove_one::<Name>(a);
+    cmd.despawn(b);
+
+    let mut undo = cmd.execute_with_inverse(&mut world, &registry);
+    let after = snapshot(&world);
+
+    assert_eq!(world.len(), 4);
+    assert!(world.contains(c));
+    assert!(!world.contains(b));
+
+    let mut redo = undo.execute_with_inverse(&mut world, &registry);
+    assert_eq!(snapshot(&world), before);
+
+    redo.execute(&mut world);
+    assert_eq!(snapshot(&world), after);
+}
+
+#[test]
+fn undo_empty_entity() {
+    #[derive(Debug, Clone, PartialEq)]
+    struct Position(i32);
+
+    let mut registry = CloneRegistry::new();
+    registry.register::<Position>();
+
+    let mut world = World::default();
+    let a = world.spawn(());
+    let b = world.spawn(());
+
+    let mut cmd = CommandBuffer::new();
+    cmd.insert_one(a, Position(1));
+    cmd.despawn(b);
+
+    let mut undo = cmd.execute_with_inverse(&mut world, &registry);
+    assert!(!world.contains(b));
+
+    undo.execute(&mut world);
+
+    // Both entities exist again, without components
+    assert!(world.entity(a).unwrap().is_empty());
+    assert!(world.entity(b).unwrap().is_empty());
+}
@@ -0,0 +1,94 @@
+use std::any::TypeId;
+
+use hecs::World;
+use hecs_schedule::{
+    filter::With, Access, DynamicSubWorld, DynamicSubWorldRef, Error, Read, Schedule, SubWorld,
+};
+
+#[derive(Debug, PartialEq)]
+struct Health(i32);
+
+#[derive(Debug, PartialEq)]
+struct Position(i32);
+
+struct Player;
+
+#[test]
+fn dynamic_access() {
+    let mut world = World::default();
+    let player = world.spawn((Player, Health(10), Position(0)));
+    let other = world.spawn((Health(5),));
+
+    let subworld = DynamicSubWorldRef::new(
+        &world,
+        [
+            Access::of::<&mut Health>().filtered::<With<Player>>(),
+            Access::of::<&Position>(),
+        ],
+    );
+
+    assert!(subworld.has::<&mut Health>());
+    assert!(!subworld.has::<&mut Position>());
+    assert!(!subworld.has::<&Player>());
+
+    subworld.get_mut::<Health>(player).unwrap().0 += 1;
+    assert!(subworld.get::<Position>(player).is_ok());
+    assert!(matches!(
+        subworld.get::<Health>(other),
+        Err(Error::UnsatisfiedQuery(..))
+    ));
+    assert!(matches!(
+        subworld.get::<Player>(player),
+        Err(Error::IncompatibleSubworld { .. })
+    ));
+
+    // `other` does not pass the filter of the health access
+    assert!(subworld.try_query::<&Health>().is_err());
+    assert!(subworld
+        .try_query::<hecs::With<&Health, &Position>>()
+        .is_ok());
+    assert_eq!(subworld.query::<&Position>().iter().count(), 1);
+
+    let ids = [TypeId::of::<Health>()];
+    assert!(subworld.query_dynamic(&ids).unwrap().eq([player]));
+    assert!(subworld.query_dynamic(&[TypeId::of::<Player>()]).is_err());
+
+    assert_eq!(*world.get::<&Health>(player).unwrap(), Health(11));
+}
+
+#[test]
+fn dynamic_system() {
+    let mut world = World::default();
+    let entity = world.spawn((Health(10), Position(0)));
+    let mut damage = 3;
+
+    let script = move |w: DynamicSubWorld, damage: Read<i32>| -> anyhow::Result<()> {
+        let health = TypeId::of::<Health>();
+        for entity in w.query_dynamic(&[health])? {
+            w.get_mut::<Health>(entity)?.0 -= *damage;
+        }
+        Ok(())
+    };
+
+    let mut schedule = Schedule::builder()
+        .add_dynamic_system([Access::of::<&mut Health>()], script)
+        // Can run in parallel with the script
+        .add_system(|w: SubWorld<&Position>| {
+            assert_eq!(w.query::<&Position>().iter().count(), 1);
+        })
+        // Has to wait for the script
+        .add_system(move |w: SubWorld<&Health>| {
+            assert_eq!(*w.get::<Health>(entity).unwrap(), Health(7));
+        })
+        .build();
+
+    assert_eq!(schedule.batch_info().len(), 3);
+    schedule.execute_seq((&mut world, &mut damage)).unwrap();
+
+    // The accesses need to be declared when adding the system
+    let mut schedule = Schedule::builder()
+        .add_system(|_: DynamicSubWorld| {})
+        .build();
+
+    assert!(schedule.execute_seq((&mut world,)).is_err());
+}
@@ -0,0 +1,61 @@
+use std::sync::{Arc, Mutex};
+
+use hecs_schedule::{borrow::ComponentBorrow, EventReader, EventWriter, Events, Schedule, Write};
+
+#[test]
+fn events() {
+    let mut events = Events::<u32>::new();
+
+    let before = Arc::new(Mutex::new(Vec::new()));
+    let after = Arc::new(Mutex::new(Vec::new()));
+
+    let mut schedule = {
+        let before = before.clone();
+        let after = after.clone();
+        Schedule::builder()
+            .add_event::<u32>()
+            .add_system(move |mut reader: EventReader<u32>| {
+                before.lock().unwrap().extend(reader.read().copied())
+            })
+            .add_system(|mut writer: EventWriter<u32>, mut counter: Write<u32>| {
+                *counter += 1;
+                writer.send_batch([*counter * 10, *counter * 10 + 1]);
+            })
+            .add_system(move |mut reader: EventReader<u32>| {
+                after.lock().unwrap().extend(reader.read().copied())
+            })
+            .build()
+    };
+
+    let mut counter = 0_u32;
+
+    schedule.execute_seq((&mut events, &mut counter)).unwrap();
+    assert_eq!(*before.lock().unwrap(), []);
+    assert_eq!(*after.lock().unwrap(), [10, 11]);
+
+    schedule.execute_seq((&mut events, &mut counter)).unwrap();
+    // Readers before the writer observe the events of the previous cycle
+    assert_eq!(*before.lock().unwrap(), [10, 11]);
+    assert_eq!(*after.lock().unwrap(), [10, 11, 20, 21]);
+    assert_eq!(events.len(), 4);
+
+    schedule.execute_seq((&mut events, &mut counter)).unwrap();
+    assert_eq!(*before.lock().unwrap(), [10, 11, 20, 21]);
+    assert_eq!(*after.lock().unwrap(), [10, 11, 20, 21, 30, 31]);
+
+    // Events are dropped after two updates
+    assert!(events.iter().copied().eq([20, 21, 30, 31]));
+    events.clear();
+    assert!(events.is_empty());
+
+    // Readers only require shared access
+    assert!(EventReader::<u32>::borrows()
+        .iter()
+        .all(|access| !access.exclusive()));
+}
+
+#[test]
+#[should_panic(expected = "share the state")]
+fn duplicate_readers() {
+    Schedule::builder().add_system(|_: EventReader<u32>, _: EventReader<u32>| {});
+}
@@ -0,0 +1,104 @@
+use hecs::World;
+use hecs_schedule::{
+    borrow::ComponentBorrow,
+    filter::{With, Without},
+    Error, Schedule, SubWorld, SubWorldRef,
+};
+
+#[derive(Debug, PartialEq)]
+struct Transform(i32);
+
+struct Player;
+struct Enemy;
+
+#[test]
+fn filtered_query() {
+    let mut world = World::default();
+    let player = world.spawn((Player, Transform(0)));
+    let other = world.spawn((Transform(0),));
+
+    let subworld = SubWorldRef::<&mut Transform, With<Player>>::new(&world);
+    for (_, transform) in subworld.query::<&mut Transform>().iter() {
+        transform.0 += 1;
+    }
+
+    assert_eq!(subworld.query::<&Transform>().iter().count(), 1);
+    assert!(subworld.get::<Transform>(player).is_ok());
+    assert!(matches!(
+        subworld.get_mut::<Transform>(other),
+        Err(Error::UnsatisfiedQuery(..))
+    ));
+    assert!(subworld
+        .query_one::<&Transform>(other)
+        .unwrap()
+        .get()
+        .is_err());
+
+    let split: SubWorldRef<(), With<Player>> = subworld.split().unwrap();
+    assert_eq!(split.query::<()>().iter().count(), 1);
+
+    let subworld = SubWorldRef::<&Transform, (Without<Player>, Without<Enemy>)>::new(&world);
+    assert!(subworld
+        .query::<&Transform>()
+        .iter()
+        .map(|(e, _)| e)
+        .eq([other]));
+
+    assert_eq!(*world.get::<&Transform>(player).unwrap(), Transform(1));
+    assert_eq!(*world.get::<&Transform>(other).unwrap(), Transform(0));
+}
+
+#[test]
+fn filtered_borrows() {
+    let borrows = SubWorld::<&mut Transform, With<Player>>::borrows();
+    let other = SubWorld::<&mut Transform, Without<Player>>::borrows();
+    let unfiltered = SubWorld::<&Transform>::borrows();
+
+    assert!(!borrows[0].conflicts(&other[0]));
+    assert!(borrows[0].conflicts(&unfiltered[0]));
+    assert!(other[0].conflicts(&unfiltered[0]));
+    assert!(!unfiltered[0].conflicts(&unfiltered[0]));
+
+    // Filtered accesses are still plain values
+    let copy = borrows[0];
+    assert_eq!(copy, SubWorld::<&mut Transform, With<Player>>::borrows()[0]);
+    assert_ne!(copy, other[0]);
+}
+
+#[test]
+fn disjoint_writes() {
+    let mut world = World::default();
+    world.spawn((Player, Transform(0)));
+    world.spawn((Transform(0),));
+
+    let mut schedule = Schedule::builder()
+        .add_system(|w: SubWorld<&mut Transform, With<Player>>| {
+            let mut query = w.query::<&mut Transform>();
+            query.iter().for_each(|(_, t)| t.0 += 1);
+        })
+        .add_system(|w: SubWorld<&mut Transform, Without<Player>>| {
+            let mut query = w.query::<&mut Transform>();
+            query.iter().for_each(|(_, t)| t.0 -= 1);
+        })
+        .add_system(|w: SubWorld<&Transform>| {
+            assert_eq!(
+                w.query::<&Transform>()
+                    .iter()
+                    .map(|(_, t)| t.0)
+                    .sum::<i32>(),
+                0
+            );
+        })
+        .build();
+
+    // The writes share a batch, while the unfiltered read and the final flush
+    // are serialised after them
+    assert_eq!(schedule.batch_info().len(), 3);
+
+    schedule.execute_seq((&mut world,)).unwrap();
+
+    let mut query = world.query::<(&Transform, Option<&Player>)>();
+    for (_, (transform, player)) in query.iter() {
+        assert_eq!(transform.0, if player.is_some() { 1 } else { -1 });
+    }
+}
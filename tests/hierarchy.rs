@@ -0,0 +1,164 @@
+use hecs::World;
+use hecs_schedule::{
+    hierarchy::{propagate, sync_children, Children, Parent, Propagate, TraversalOrder},
+    CommandBuffer, CommandKind, Error, Schedule, SubWorldRef, Write,
+};
+
+#[test]
+fn despawn_recursive() {
+    let mut world = World::default();
+
+    let root = world.spawn(("root",));
+    let a = world.spawn(("a", Parent(root)));
+    let b = world.spawn(("b", Parent(root)));
+    let c = world.spawn(("c", Parent(a)));
+    let other = world.spawn(("other",));
+
+    let mut schedule = Schedule::builder().add_system(sync_children).build();
+    // Children are inserted on the first flush
+    schedule.execute_seq((&mut world,)).unwrap();
+
+    assert_eq!(world.get::<&Children>(root).unwrap().as_slice(), [a, b]);
+    assert_eq!(world.get::<&Children>(a).unwrap().as_slice(), [c]);
+
+    let mut cmd = CommandBuffer::new();
+    cmd.despawn_recursive(a);
+    cmd.execute(&mut world);
+
+    assert!(!world.contains(a));
+    assert!(!world.contains(c));
+    assert_eq!(world.get::<&Children>(root).unwrap().as_slice(), [b]);
+
+    // Reparent b
+    world.insert_one(b, Parent(other)).unwrap();
+    schedule.execute_seq((&mut world,)).unwrap();
+
+    assert!(world.get::<&Children>(root).unwrap().is_empty());
+    assert_eq!(world.get::<&Children>(other).unwrap().as_slice(), [b]);
+
+    cmd.despawn_recursive(other);
+    cmd.execute(&mut world);
+
+    assert!(world.iter().map(|e| e.entity()).eq([root]));
+}
+
+#[test]
+fn set_parent() {
+    let mut world = World::default();
+
+    let root = world.spawn(("root",));
+    let a = world.spawn(("a",));
+    let b = world.spawn(("b",));
+    let c = world.spawn(("c",));
+
+    let mut cmd = CommandBuffer::new();
+    cmd.set_parent(a, root);
+    cmd.set_parent(b, root);
+    cmd.set_parent(c, a);
+    cmd.try_execute(&mut world).unwrap();
+
+    assert_eq!(world.get::<&Children>(root).unwrap().as_slice(), [a, b]);
+    assert_eq!(*world.get::<&Parent>(c).unwrap(), Parent(a));
+
+    // Reparent c and detach b
+    cmd.set_parent(c, b);
+    cmd.remove_parent(b);
+    cmd.try_execute(&mut world).unwrap();
+
+    assert_eq!(world.get::<&Children>(root).unwrap().as_slice(), [a]);
+    assert!(world.get::<&Children>(a).unwrap().is_empty());
+    assert_eq!(world.get::<&Children>(b).unwrap().as_slice(), [c]);
+    assert!(world.get::<&Parent>(b).is_err());
+
+    // Cycles are rejected
+    cmd.set_parent(b, c);
+    let errors = cmd.try_execute(&mut world).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, CommandKind::Insert);
+    assert!(matches!(errors[0].error, Error::HierarchyCycle(..)));
+    assert!(world.get::<&Parent>(b).is_err());
+}
+
+#[test]
+fn descendants() {
+    let mut world = World::default();
+
+    let root = world.spawn(("root",));
+    let a = world.spawn(("a",));
+    let b = world.spawn(("b",));
+    let c = world.spawn(("c",));
+    let d = world.spawn(("d",));
+
+    let mut cmd = CommandBuffer::new();
+    cmd.set_parent(a, root);
+    cmd.set_parent(b, root);
+    cmd.set_parent(c, a);
+    cmd.set_parent(d, b);
+    cmd.execute(&mut world);
+
+    let subworld = SubWorldRef::<&Children>::new(&world);
+
+    let depth_first: Vec<_> = subworld
+        .descendants(root, TraversalOrder::DepthFirst)
+        .unwrap()
+        .collect();
+    assert_eq!(depth_first, [a, c, b, d]);
+
+    let breadth_first: Vec<_> = subworld
+        .descendants(root, TraversalOrder::BreadthFirst)
+        .unwrap()
+        .collect();
+    assert_eq!(breadth_first, [a, b, c, d]);
+
+    let empty = SubWorldRef::<&Parent>::new(&world);
+    assert!(empty.descendants(root, TraversalOrder::DepthFirst).is_err());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Local(f32);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Global(f32);
+
+impl Propagate for Local {
+    type Output = Global;
+
+    fn root(&self) -> Global {
+        Global(self.0)
+    }
+
+    fn propagate(&self, parent: &Global) -> Global {
+        Global(parent.0 + self.0)
+    }
+}
+
+#[test]
+fn propagate_values() {
+    let mut world = World::default();
+
+    let root = world.spawn((Local(1.0), Global(0.0)));
+    let a = world.spawn((Local(2.0), Global(0.0)));
+    let b = world.spawn((Local(3.0), Global(0.0)));
+    // Intermediate entities without output still propagate
+    let c = world.spawn((Local(4.0),));
+    let d = world.spawn((Local(5.0), Global(0.0)));
+
+    let mut schedule = Schedule::builder()
+        .add_system(move |mut cmd: Write<CommandBuffer>| {
+            cmd.set_parent(a, root);
+            cmd.set_parent(b, a);
+            cmd.set_parent(c, root);
+            cmd.set_parent(d, c);
+        })
+        .add_hierarchy_systems()
+        .add_system(propagate::<Local>)
+        .build();
+
+    schedule.execute_seq((&mut world,)).unwrap();
+
+    let global = |e| *world.get::<&Global>(e).unwrap();
+    assert_eq!(global(root), Global(1.0));
+    assert_eq!(global(a), Global(3.0));
+    assert_eq!(global(b), Global(6.0));
+    assert_eq!(global(d), Global(10.0));
+}
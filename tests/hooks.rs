@@ -0,0 +1,104 @@
+use std::sync::{Arc, Mutex};
+
+use hecs::{Entity, World};
+use hecs_schedule::{CommandBuffer, Hooks, Schedule, Write};
+
+type Log = Arc<Mutex<Vec<(&'static str, Entity)>>>;
+
+fn log(log: &Log, event: &'static str) -> impl Fn(&mut World, Entity) + Send + Sync + 'static {
+    let log = log.clone();
+    move |_, entity| log.lock().unwrap().push((event, entity))
+}
+
+#[test]
+fn hooks() {
+    let mut hooks = Hooks::new();
+    let events: Log = Default::default();
+
+    hooks
+        .on_add::<i32>(log(&events, "add"))
+        .on_insert::<i32>(log(&events, "insert"))
+        .on_remove::<i32>({
+            let events = events.clone();
+            // The value is still present when the hook runs
+            move |world, entity| {
+                assert!(world.get::<&i32>(entity).is_ok());
+                events.lock().unwrap().push(("remove", entity))
+            }
+        });
+
+    let mut world = World::default();
+    let a = world.spawn(("a",));
+
+    let mut cmd = CommandBuffer::new();
+    let b = cmd.spawn_reserved(&world, (1_i32,));
+    cmd.insert(a, (1_i32,));
+    cmd.insert_one(a, 2_i32);
+    cmd.insert_one(a, "a");
+    cmd.remove_one::<i32>(a);
+    cmd.despawn(b);
+    cmd.try_execute_with_hooks(&mut world, &hooks).unwrap();
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            ("add", b),
+            ("insert", b),
+            ("add", a),
+            ("insert", a),
+            // Replacing only runs `on_insert`
+            ("insert", a),
+            ("remove", a),
+            ("remove", b),
+        ]
+    );
+
+    // Hooks are not run without the registry
+    events.lock().unwrap().clear();
+    cmd.insert_one(a, 3_i32);
+    cmd.try_execute(&mut world).unwrap();
+    assert!(events.lock().unwrap().is_empty());
+
+    // Removing a bundle which is not fully present removes nothing
+    cmd.remove::<(i32, f32)>(a);
+    assert!(cmd.try_execute_with_hooks(&mut world, &hooks).is_err());
+    assert!(world.get::<&i32>(a).is_ok());
+    assert!(events.lock().unwrap().is_empty());
+}
+
+#[test]
+fn schedule_hooks() {
+    let events: Log = Default::default();
+
+    let mut schedule = Schedule::builder()
+        .on_add::<i32>(log(&events, "add"))
+        .on_remove::<i32>(log(&events, "remove"))
+        .add_system(|mut cmd: Write<CommandBuffer>, mut counter: Write<usize>| {
+            *counter += 1;
+            match *counter {
+                1 => {
+                    cmd.spawn((1_i32, "a"));
+                }
+                _ => cmd.write(|world: &mut World| world.clear()),
+            }
+        })
+        .build();
+
+    let mut world = World::default();
+    let mut counter = 0_usize;
+
+    schedule.execute_seq((&mut world, &mut counter)).unwrap();
+    let (a, _) = world.query_mut::<&i32>().into_iter().next().unwrap();
+    assert_eq!(*events.lock().unwrap(), [("add", a)]);
+
+    // Custom writes do not run hooks
+    schedule.execute_seq((&mut world, &mut counter)).unwrap();
+    assert_eq!(*events.lock().unwrap(), [("add", a)]);
+
+    // Hooks can be added after building
+    schedule.hooks_mut().on_add::<&str>(log(&events, "add str"));
+    let mut world = World::default();
+    counter = 0;
+    schedule.execute_seq((&mut world, &mut counter)).unwrap();
+    assert_eq!(events.lock().unwrap().len(), 3);
+}
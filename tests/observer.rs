@@ -0,0 +1,101 @@
+use std::sync::{Arc, Mutex};
+
+use hecs::{Entity, World};
+use hecs_schedule::{
+    CommandBuffer, OnAdd, OnRemove, Read, Schedule, SubWorld, Trigger, Triggered, Write,
+};
+
+struct Damage(i32);
+
+impl Trigger for Damage {}
+
+#[derive(Debug, PartialEq)]
+struct Health(i32);
+
+#[test]
+fn observers() {
+    let mut world = World::default();
+    let a = world.spawn((Health(10),));
+
+    let added: Arc<Mutex<Vec<Entity>>> = Default::default();
+    let removed: Arc<Mutex<Vec<Entity>>> = Default::default();
+
+    let mut schedule = {
+        let added = added.clone();
+        let removed = removed.clone();
+        Schedule::builder()
+            .add_system(move |mut cmd: Write<CommandBuffer>, counter: Read<usize>| {
+                if *counter & 1 == 0 {
+                    cmd.trigger_for(a, Damage(3));
+                }
+            })
+            .observe::<Damage, _, _, _>(
+                |trigger: Triggered<Damage>,
+                 world: SubWorld<&mut Health>,
+                 mut cmd: Write<CommandBuffer>| {
+                    let entity = trigger.entity().unwrap();
+                    let Ok(mut health) = world.get_mut::<Health>(entity) else {
+                        return;
+                    };
+                    health.0 -= trigger.event().0;
+
+                    if health.0 <= 0 {
+                        cmd.remove_one::<Health>(entity);
+                    }
+                },
+            )
+            .observe::<OnAdd<i32>, _, _, _>(move |trigger: Triggered<OnAdd<i32>>| {
+                added.lock().unwrap().push(trigger.entity().unwrap())
+            })
+            .observe::<OnRemove<Health>, _, _, _>(move |trigger: Triggered<OnRemove<Health>>| {
+                removed.lock().unwrap().push(trigger.entity().unwrap())
+            })
+            .add_system(|mut counter: Write<usize>| *counter += 1)
+            .build()
+    };
+
+    let mut counter = 0_usize;
+
+    schedule.execute_seq((&mut world, &mut counter)).unwrap();
+    assert_eq!(*world.get::<&Health>(a).unwrap(), Health(7));
+
+    // Observers only run when triggered
+    schedule.execute_seq((&mut world, &mut counter)).unwrap();
+    assert_eq!(*world.get::<&Health>(a).unwrap(), Health(7));
+
+    for _ in 0..6 {
+        schedule.execute_seq((&mut world, &mut counter)).unwrap();
+    }
+
+    // Commands of observers are applied by the next flush
+    assert!(world.get::<&Health>(a).is_err());
+    assert_eq!(*removed.lock().unwrap(), [a]);
+
+    schedule.cmd_mut().insert_one(a, 1_i32);
+    schedule.execute_seq((&mut world, &mut counter)).unwrap();
+    assert_eq!(*added.lock().unwrap(), [a]);
+}
+
+struct Ping;
+
+impl Trigger for Ping {}
+
+#[test]
+fn observers_after_own_flush() {
+    let mut world = World::default();
+    let pings = Arc::new(Mutex::new(0));
+
+    let mut schedule = {
+        let pings = pings.clone();
+        Schedule::builder()
+            .add_system(|mut cmd: Write<CommandBuffer>| cmd.trigger(Ping))
+            .observe::<Ping, _, _, _>(move |_: Triggered<Ping>| *pings.lock().unwrap() += 1)
+            .build()
+    };
+
+    // The flush conflicts with the system, and thus runs in a batch of its own
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert_eq!(*pings.lock().unwrap(), 1);
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert_eq!(*pings.lock().unwrap(), 2);
+}
@@ -0,0 +1,123 @@
+use hecs::World;
+use hecs_schedule::{
+    filter::With, CommandBuffer, EntityScope, Error, Schedule, SubWorld, SubWorldRef, Write,
+};
+
+#[derive(Debug, PartialEq)]
+struct Health(i32);
+
+struct Red;
+struct Blue;
+
+fn heal_red(w: SubWorld<&mut Health, With<Red>>) {
+    w.query::<&mut Health>().iter().for_each(|(_, h)| h.0 += 1);
+}
+
+fn heal_blue(w: SubWorld<&mut Health, With<Blue>>) {
+    w.query::<&mut Health>().iter().for_each(|(_, h)| h.0 += 2);
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+fn partitioned_systems() {
+    let mut world = World::default();
+    let red = world.spawn((Red, Health(0)));
+    let blue = world.spawn((Blue, Health(0)));
+
+    let mut schedule = Schedule::builder()
+        .partition::<(Red, Blue)>()
+        .add_system(heal_red)
+        .add_system(heal_blue)
+        .build();
+
+    // Both systems and the final flush
+    assert_eq!(schedule.batch_info().len(), 2);
+    schedule.execute((&mut world,)).unwrap();
+
+    assert_eq!(*world.get::<&Health>(red).unwrap(), Health(1));
+    assert_eq!(*world.get::<&Health>(blue).unwrap(), Health(2));
+
+    // Without the partition, an entity may be both red and blue
+    let schedule = Schedule::builder()
+        .add_system(heal_red)
+        .add_system(heal_blue)
+        .build();
+
+    assert_eq!(schedule.batch_info().len(), 3);
+
+    // Partitions declared after the systems, or by an appended builder
+    let schedule = Schedule::builder()
+        .add_system(heal_red)
+        .add_system(heal_blue)
+        .partition::<(Red, Blue)>()
+        .build();
+
+    assert_eq!(schedule.batch_info().len(), 2);
+
+    let mut teams = Schedule::builder();
+    teams.partition::<(Red, Blue)>();
+    let schedule = Schedule::builder()
+        .add_system(heal_red)
+        .add_system(heal_blue)
+        .append(&mut teams)
+        .build();
+
+    assert_eq!(schedule.batch_info().len(), 2);
+}
+
+#[test]
+fn overlapping_partition() {
+    let mut world = World::default();
+    let entity = world.spawn((Red, Health(0)));
+
+    let mut schedule = Schedule::builder()
+        .partition::<(Red, Blue)>()
+        .add_system(move |mut cmd: Write<CommandBuffer>| cmd.insert_one(entity, Blue))
+        .flush()
+        .add_system(heal_red)
+        .add_system(heal_blue)
+        .build();
+
+    assert!(matches!(
+        schedule.execute_seq((&mut world,)),
+        Err(Error::OverlappingPartition(e, _)) if e == entity
+    ));
+    assert_eq!(*world.get::<&Health>(entity).unwrap(), Health(0));
+
+    // Only batches relying on the partition are checked
+    let mut schedule = Schedule::builder()
+        .partition::<(Red, Blue)>()
+        .add_system(heal_red)
+        .build();
+
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert_eq!(*world.get::<&Health>(entity).unwrap(), Health(1));
+}
+
+#[test]
+fn scoped() {
+    let mut world = World::default();
+    let inside = world.spawn((Health(0),));
+    let outside = world.spawn((Health(0),));
+
+    let scope: EntityScope = [inside].into_iter().collect();
+    let subworld = SubWorldRef::<&mut Health>::new(&world);
+    let scoped = subworld.scoped(&scope);
+
+    for (_, health) in scoped.query::<&mut Health>().iter() {
+        health.0 += 1;
+    }
+
+    assert_eq!(*scoped.get::<Health>(inside).unwrap(), Health(1));
+    assert!(matches!(
+        scoped.get_mut::<Health>(outside),
+        Err(Error::OutOfScope(e)) if e == outside
+    ));
+    assert!(matches!(
+        scoped.query_one::<&Health>(outside),
+        Err(Error::OutOfScope(_))
+    ));
+    assert!(scoped.query_one::<&Health>(inside).is_ok());
+
+    assert_eq!(*world.get::<&Health>(outside).unwrap(), Health(0));
+}
@@ -0,0 +1,99 @@
+use std::sync::{Arc, Mutex};
+
+use hecs::{Entity, World};
+use hecs_schedule::{CommandBuffer, RemovedComponents, Schedule, Trigger, Triggered, Write};
+
+type Seen = Arc<Mutex<Vec<Entity>>>;
+
+#[test]
+fn removed_components() {
+    let mut world = World::default();
+
+    let a = world.spawn((1_i32, "a"));
+    let b = world.spawn((2_i32, "b"));
+    let c = world.spawn((3_i32, "c"));
+    let d = world.spawn((4_i32, "d"));
+
+    let before: Seen = Default::default();
+    let after: Seen = Default::default();
+    let commands = Arc::new(Mutex::new(CommandBuffer::new()));
+
+    let record = |seen: &Seen| {
+        let seen = seen.clone();
+        move |removed: RemovedComponents<i32>| {
+            let mut seen = seen.lock().unwrap();
+            seen.clear();
+            seen.extend(removed.iter());
+        }
+    };
+
+    let mut schedule = {
+        let commands = commands.clone();
+        Schedule::builder()
+            .add_system(record(&before))
+            .add_system(move |mut cmd: Write<CommandBuffer>| {
+                cmd.append(std::mem::take(&mut *commands.lock().unwrap()))
+            })
+            .flush()
+            .add_system(record(&after))
+            .build()
+    };
+
+    commands.lock().unwrap().remove_one::<i32>(a);
+    commands.lock().unwrap().despawn(b);
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert!(before.lock().unwrap().is_empty());
+    assert_eq!(*after.lock().unwrap(), [a, b]);
+
+    // Observed once by the systems which ran before the flush
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert_eq!(*before.lock().unwrap(), [a, b]);
+    assert!(after.lock().unwrap().is_empty());
+
+    // Removing other components is not observed
+    commands.lock().unwrap().remove_one::<&str>(c);
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert!(before.lock().unwrap().is_empty());
+    assert!(after.lock().unwrap().is_empty());
+
+    // Custom writes
+    commands.lock().unwrap().write(move |world| {
+        world.remove_one::<i32>(d).unwrap();
+    });
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert!(before.lock().unwrap().is_empty());
+    assert_eq!(*after.lock().unwrap(), [d]);
+
+    // Exclusive edits outside the schedule
+    schedule.removals().despawn(&mut world, c).unwrap();
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert_eq!(*before.lock().unwrap(), [d, c]);
+    assert_eq!(*after.lock().unwrap(), [c]);
+}
+
+struct Ping;
+
+impl Trigger for Ping {}
+
+#[test]
+fn removals_read_by_observers() {
+    let mut world = World::default();
+    let a = world.spawn((1_i32,));
+
+    let seen: Seen = Default::default();
+    let mut schedule = {
+        let seen = seen.clone();
+        Schedule::builder()
+            .add_system(move |mut cmd: Write<CommandBuffer>| {
+                cmd.remove_one::<i32>(a);
+                cmd.trigger(Ping);
+            })
+            .observe::<Ping, _, _, _>(move |_: Triggered<Ping>, removed: RemovedComponents<i32>| {
+                seen.lock().unwrap().extend(removed.iter())
+            })
+            .build()
+    };
+
+    schedule.execute_seq((&mut world,)).unwrap();
+    assert_eq!(*seen.lock().unwrap(), [a]);
+}
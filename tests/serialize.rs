@@ -0,0 +1,54 @@
+#![cfg(feature = "serde")]
+use hecs::World;
+use hecs_schedule::serialize::{ComponentRegistry, SerializableCommandBuffer};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Position(f32, f32);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Health(i32);
+
+#[test]
+fn replicate() {
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register::<Position>("position")
+        .register::<Health>("health");
+
+    let mut server = World::default();
+    let mut client = World::default();
+
+    let mut cmd = SerializableCommandBuffer::new();
+    let a = cmd.spawn_reserved(&server, (Position(1.0, 2.0), Health(100)));
+    let b = cmd.spawn_reserved(&server, (Position(0.0, 0.0),));
+    cmd.insert_one(a, Position(5.0, 3.0));
+    cmd.remove_one::<Health>(a);
+    cmd.despawn(b);
+
+    let bytes = serde_json::to_vec(&registry.serialize(&cmd)).unwrap();
+
+    cmd.into_command_buffer(&registry).execute(&mut server);
+
+    registry
+        .deserialize(&mut serde_json::Deserializer::from_slice(&bytes))
+        .unwrap()
+        .try_execute(&mut client)
+        .unwrap();
+
+    for world in [&server, &client] {
+        assert_eq!(*world.get::<&Position>(a).unwrap(), Position(5.0, 3.0));
+        assert!(world.get::<&Health>(a).is_err());
+        assert!(!world.contains(b));
+    }
+}
+
+#[test]
+fn unregistered_component() {
+    let registry = ComponentRegistry::new();
+
+    let mut cmd = SerializableCommandBuffer::new();
+    cmd.spawn((Health(5),));
+
+    assert!(serde_json::to_vec(&registry.serialize(&cmd)).is_err());
+}
@@ -0,0 +1,140 @@
+use std::sync::{Arc, Mutex};
+
+use hecs_schedule::{in_state, NextState, Read, Schedule, State, System, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flow {
+    Menu,
+    Playing,
+    Paused,
+}
+
+fn request_transitions(
+    mut next: Write<NextState<Flow>>,
+    state: Read<State<Flow>>,
+    frame: Read<usize>,
+) {
+    match (*state.get(), *frame) {
+        (Flow::Menu, 1) => next.set(Flow::Playing),
+        (Flow::Playing, 3) => next.set(Flow::Paused),
+        (Flow::Paused, 4) => next.set(Flow::Paused),
+        _ => {}
+    }
+}
+
+#[test]
+fn states() {
+    let log: Arc<Mutex<Vec<String>>> = Default::default();
+    let push = |msg: &'static str| {
+        let log = log.clone();
+        move || log.lock().unwrap().push(msg.to_string())
+    };
+
+    let mut schedule = Schedule::builder()
+        .add_system(request_transitions)
+        .add_state::<Flow>()
+        .on_enter(Flow::Menu, push("enter menu"))
+        .on_exit(Flow::Menu, push("exit menu"))
+        .on_enter(Flow::Playing, push("enter playing"))
+        .on_update(Flow::Playing, push("update playing"))
+        .on_exit(Flow::Playing, push("exit playing"))
+        .on_enter(Flow::Paused, push("enter paused"))
+        .add_system(push("menu").run_if(in_state(Flow::Menu)))
+        .add_system(|mut frame: Write<usize>| *frame += 1)
+        .build();
+
+    let mut state = State::new(Flow::Menu);
+    let mut next = NextState::<Flow>::new();
+    let mut frame = 0_usize;
+
+    let mut expect = |expected: &[&str]| {
+        schedule
+            .execute_seq((&mut state, &mut next, &mut frame))
+            .unwrap();
+        assert_eq!(*log.lock().unwrap(), expected);
+        log.lock().unwrap().clear();
+    };
+
+    expect(&["enter menu", "menu"]);
+    expect(&["exit menu", "enter playing", "update playing"]);
+    expect(&["update playing"]);
+    expect(&["exit playing", "enter paused"]);
+    // Transitions to the current state are ignored
+    expect(&[]);
+
+    assert_eq!(*state.get(), Flow::Paused);
+    assert_eq!(next.get(), None);
+}
+
+#[test]
+fn append() {
+    let log: Arc<Mutex<Vec<String>>> = Default::default();
+    let push = |msg: &'static str| {
+        let log = log.clone();
+        move || log.lock().unwrap().push(msg.to_string())
+    };
+
+    let expect = |schedule: &mut Schedule, expected: &[&str]| {
+        let mut state = State::new(Flow::Menu);
+        schedule.execute_seq((&mut state,)).unwrap();
+        assert_eq!(*log.lock().unwrap(), expected);
+        log.lock().unwrap().clear();
+    };
+
+    // The transition point is added by the appending builder
+    let mut plugin = Schedule::builder();
+    plugin
+        .on_enter(Flow::Menu, push("plugin enter"))
+        .on_update(Flow::Menu, push("plugin update"));
+
+    let mut schedule = Schedule::builder()
+        .add_state::<Flow>()
+        .on_enter(Flow::Menu, push("enter"))
+        .append(&mut plugin)
+        .build();
+
+    expect(&mut schedule, &["enter", "plugin enter", "plugin update"]);
+
+    // The transition point is added by the appended builder
+    let mut plugin = Schedule::builder();
+    plugin
+        .add_state::<Flow>()
+        .on_enter(Flow::Menu, push("plugin enter"));
+
+    let mut schedule = Schedule::builder()
+        .on_enter(Flow::Menu, push("enter"))
+        .on_update(Flow::Menu, push("update"))
+        .append(&mut plugin)
+        .build();
+
+    expect(&mut schedule, &["enter", "plugin enter", "update"]);
+}
+
+#[cfg(feature = "parallel")]
+fn increment(mut x: Write<u32>) {
+    // Holds the borrow long enough for a parallel system to run into it
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    *x += 1;
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+fn append_parallel() {
+    // The systems of an appended state do not share a batch with the other
+    // systems, even though the transition only declares the state
+    let mut plugin = Schedule::builder();
+    plugin.add_state::<Flow>().on_update(Flow::Menu, increment);
+
+    let mut schedule = Schedule::builder()
+        .add_system(increment)
+        .append(&mut plugin)
+        .build();
+
+    let mut state = State::new(Flow::Menu);
+    let mut x = 0_u32;
+    for _ in 0..4 {
+        schedule.execute((&mut state, &mut x)).unwrap();
+    }
+
+    assert_eq!(x, 8);
+}
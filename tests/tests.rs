@@ -1,6 +1,6 @@
 use std::{thread::sleep, time::Duration};
 
-use anyhow::{bail, ensure};
+use anyhow::bail;
 use atomic_refcell::AtomicRefCell;
 use hecs::{Query, World};
 use hecs_schedule::{traits::QueryExt, *};
@@ -14,7 +14,7 @@ fn has() {
     let subworld = SubWorldRef::<(&i32, &mut f32, &String)>::new(&world);
     let subworld = &subworld;
 
-    let subworld: SubWorldRef<(&i32, &mut f32)> = subworld.try_into().unwrap();
+    let subworld: SubWorldRef<(&i32, &mut f32)> = subworld.into();
 
     assert!(subworld.has::<&i32>());
     assert!(!subworld.has::<&mut i32>());
@@ -32,7 +32,7 @@ fn query() {
     let mut world = World::default();
 
     world.spawn((67_i32, 7.0_f32));
-    let entity = world.spawn((42_i32, 3.1415_f32));
+    let entity = world.spawn((42_i32, 2.5_f32));
 
     let subworld = SubWorldRef::<(&i32, &mut f32)>::new(&world);
 
@@ -44,6 +44,181 @@ fn query() {
     assert_eq!(*val, 42);
 }
 
+#[test]
+fn static_query() {
+    let mut world = World::default();
+
+    world.spawn((67_i32, 7.0_f32));
+    let entity = world.spawn((42_i32, 2.5_f32, "a"));
+
+    let subworld = SubWorldRef::<(&i32, &mut f32, &&'static str)>::new(&world);
+
+    for (_, (a, b)) in subworld.query_static::<(&i32, &mut f32), _>().iter() {
+        *b += *a as f32;
+    }
+
+    let mut query = subworld
+        .query_one_static::<(&f32, Option<&&'static str>), _>(entity)
+        .unwrap();
+    assert_eq!(query.get().unwrap(), (&44.5, Some(&"a")));
+    drop(query);
+
+    *subworld.get_mut_static::<f32, _>(entity).unwrap() = 1.0;
+    assert_eq!(*subworld.get_static::<f32, _>(entity).unwrap(), 1.0);
+
+    let split = subworld.split_static::<(&i32, &f32), _>();
+    assert_eq!(split.query_static::<&i32, _>().iter().count(), 2);
+
+    let all = SubWorldRef::<AllAccess>::new(&world);
+    assert_eq!(all.query_static::<(&mut i32, &u64), _>().iter().count(), 0);
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+fn query_par() {
+    use rayon::iter::ParallelIterator;
+
+    let mut world = World::default();
+    world.spawn_batch((0..100).map(|i| (i, i as f32)));
+    world.spawn_batch((0..100).map(|i| (i, i as f32, "a")));
+    world.spawn((1_i32,));
+
+    let subworld = SubWorldRef::<(&i32, &mut f32)>::new(&world);
+
+    let mut query = subworld.query_par::<(&i32, &mut f32)>().with_batch_size(16);
+    query.par_iter().for_each(|(_, (a, b))| *b += *a as f32);
+    drop(query);
+
+    let sum = subworld
+        .query_par::<&f32>()
+        .par_iter()
+        .map(|(_, val)| *val as i64)
+        .reduce(|| 0, |a, b| a + b);
+    assert_eq!(sum, 2 * 2 * (0..100).sum::<i64>());
+
+    let entities: Vec<_> = subworld
+        .query_par::<&i32>()
+        .par_iter()
+        .map(|(e, _)| e)
+        .collect();
+    assert_eq!(entities.len(), 201);
+
+    // The access of the subworld is respected
+    let result = std::panic::catch_unwind(|| subworld.query_par::<&mut i32>().par_iter().count());
+    assert!(result.is_err());
+}
+
+#[test]
+#[cfg(not(feature = "parallel"))]
+#[allow(deprecated)]
+fn query_par_sequential() {
+    let mut world = World::default();
+    world.spawn_batch((0..100).map(|i| (i,)));
+
+    let subworld = SubWorldRef::<&i32>::new(&world);
+    assert_eq!(subworld.query_par::<&i32>().iter().count(), 100);
+}
+
+#[test]
+fn par_map_reduce() {
+    let mut world = World::default();
+    world.spawn_batch((0..100).map(|i| (i,)));
+    world.spawn_batch((100..200).map(|i| (i, 0.5_f32)));
+
+    let sequential: Vec<i32> = world.query::<&i32>().iter().map(|(_, v)| *v).collect();
+    let mapped = world.query::<&i32>().par_map_collect(7, |(_, v)| *v);
+    assert_eq!(mapped, sequential);
+
+    let partial = world
+        .query::<&i32>()
+        .par_fold(50, || 0, |acc, (_, v)| acc + *v);
+    assert_eq!(partial.len(), 4);
+    assert_eq!(partial[0], (0..50).sum::<i32>());
+
+    let concat = world.query::<&i32>().par_reduce(
+        16,
+        String::new,
+        |acc, (_, v)| acc + &v.to_string(),
+        |a, b| a + &b,
+    );
+    let expected: String = sequential.iter().map(|v| v.to_string()).collect();
+    assert_eq!(concat, expected);
+
+    // Each batch is seeded once, without an extra seed for the reduction
+    let seeded = world
+        .query::<&i32>()
+        .par_reduce(50, || 1, |acc, _| acc + 1, |a, b| a + b);
+    assert_eq!(seeded, 200 + 4);
+    let empty = world
+        .query::<&u8>()
+        .par_reduce(50, || 1, |acc, _| acc + 1, |a, b| a + b);
+    assert_eq!(empty, 1);
+
+    let sum: i64 = world.query::<&i32>().par_sum(9, |(_, v)| *v as i64);
+    assert_eq!(sum, (0..200).sum::<i64>());
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+fn adaptive_batch_size() {
+    let mut world = World::default();
+    world.spawn_batch((0..1000).map(|i| (i,)));
+
+    let threads = rayon::current_num_threads();
+    let tasks = threads as u32 * BatchSize::TASKS_PER_THREAD;
+
+    let plan = world.query::<&i32>().batch_plan(BatchSize::adaptive());
+    assert_eq!(plan.entities, 1000);
+    assert_eq!(plan.threads, threads);
+    assert_eq!(plan.archetypes, 1);
+    assert_eq!(plan.size, (1000 - 1) / tasks + 1);
+    assert!(plan.batches <= tasks);
+    eprintln!("{plan}");
+
+    let bounded = BatchSize::Adaptive { min: 600, max: 800 };
+    assert_eq!(world.query::<&i32>().batch_plan(bounded).size, 600);
+    let bounded = BatchSize::Adaptive { min: 1, max: 2 };
+    assert_eq!(world.query::<&i32>().batch_plan(bounded).size, 2);
+    assert_eq!(world.query::<&i32>().batch_plan(64).size, 64);
+
+    let sum: i64 = world
+        .query::<&i32>()
+        .par_sum(BatchSize::adaptive(), |(_, v)| *v as i64);
+    assert_eq!(sum, (0..1000).sum::<i64>());
+
+    fn check(world: &World) -> Result<(), i32> {
+        world
+            .query::<&i32>()
+            .try_par_for_each(BatchSize::default(), |(_, v)| match *v {
+                v if v < 0 => Err(v),
+                _ => Ok(()),
+            })
+    }
+    assert_eq!(check(&world), Ok(()));
+
+    let mut query = world.query::<&mut i32>();
+    assert_eq!(query.batch_plan(BatchSize::default()), plan);
+    query.par_for_each(BatchSize::default(), |(_, v)| *v += 1);
+    drop(query);
+    assert_eq!(world.query::<&i32>().iter().map(|(_, v)| *v).min(), Some(1));
+
+    let subworld = SubWorldRef::<&i32>::new(&world);
+    let mut query = subworld.query_par::<&i32>();
+    assert_eq!(query.plan().size, plan.size);
+    assert_eq!(rayon::iter::ParallelIterator::count(query.par_iter()), 1000);
+    assert_eq!(query.plan(), plan);
+    drop(query);
+
+    // Each archetype is split separately, so a small archetype does not add
+    // a task to the larger one
+    let small = tasks + 1;
+    world.spawn_batch((0..small).map(|i| (i as i32, i as f32)));
+    let plan = world.query::<&i32>().batch_plan(BatchSize::adaptive());
+    assert_eq!(plan.archetypes, 2);
+    assert!(plan.batches <= tasks.max(2));
+    assert!(plan.size > (1000 + small - 1) / tasks);
+}
+
 #[test]
 fn custom_query() {
     let mut world = World::default();
@@ -55,7 +230,7 @@ fn custom_query() {
     }
 
     world.spawn((67_i32, 7.0_f32));
-    let entity = world.spawn((42_i32, 3.1415_f32));
+    let entity = world.spawn((42_i32, 2.5_f32));
 
     let subworld = SubWorldRef::<(Foo, &&'static str)>::new(&world);
 
@@ -85,7 +260,7 @@ fn custom_query() {
 fn fail_query() {
     let mut world = World::default();
 
-    let entity = world.spawn((42_i32, 3.1415_f32));
+    let entity = world.spawn((42_i32, 2.5_f32));
 
     let subworld = SubWorldRef::<(&i32, &f32)>::new(&world);
 
@@ -229,6 +404,39 @@ fn split() {
     assert!(b.try_query::<&i32>().is_err());
 }
 
+#[test]
+fn split_disjoint() {
+    let mut world = World::default();
+    world.spawn((1_i32, 1.0_f32, "a"));
+
+    let subworld = SubWorldRef::<(&mut i32, &mut f32, &&'static str)>::new(&world);
+    let (ints, floats) = subworld
+        .split_disjoint::<(&mut i32, &&'static str), (&mut f32, &&'static str)>()
+        .unwrap();
+
+    std::thread::scope(|s| {
+        s.spawn(|| ints.query::<&mut i32>().iter().for_each(|(_, v)| *v += 1));
+        s.spawn(|| {
+            floats
+                .query::<&mut f32>()
+                .iter()
+                .for_each(|(_, v)| *v += 1.0)
+        });
+    });
+
+    assert!(subworld
+        .query::<(&i32, &f32)>()
+        .iter()
+        .map(|(_, (a, b))| (*a, *b))
+        .eq([(2, 2.0)]));
+
+    assert!(matches!(
+        subworld.split_disjoint::<&mut i32, &i32>(),
+        Err(Error::ConflictingSplit(..))
+    ));
+    assert!(subworld.split_disjoint::<&mut i32, &mut u64>().is_err());
+}
+
 #[test]
 fn atomic() {
     let world = AtomicRefCell::new(World::default());